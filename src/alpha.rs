@@ -0,0 +1,97 @@
+use image::{GrayAlphaImage, GrayImage, ImageBuffer, Luma, LumaA, Rgb, RgbImage, Rgba, RgbaImage};
+
+use crate::color::{linear_to_srgb, srgb_to_linear};
+use crate::{DiffusionKernel, Ditherer, ErrorDiffusion, Grayscale};
+
+/// Blends every pixel of `img` over `background`, as an image viewer shows
+/// it, so transparent areas dither as the background instead of whatever
+/// color they hold
+///
+/// Colors are mixed in linear light, like dithering, so half transparent
+/// edges keep their brightness
+///
+/// ## Returns
+/// RgbaImage buffer of fully opaque pixels
+pub fn composite(img: &RgbaImage, background: Rgb<u8>) -> RgbaImage {
+    ImageBuffer::from_fn(img.width(), img.height(), |x, y| {
+        let Rgba([r, g, b, a]) = *img.get_pixel(x, y);
+        let [br, bg, bb] = background.0;
+        let a = f32::from(a) / 255.0;
+        let decode = |c: u8| srgb_to_linear(f32::from(c) / 255.0);
+        let blend = |c: u8, back: u8| {
+            let light = decode(c) * a + decode(back) * (1.0 - a);
+            (linear_to_srgb(light) * 255.0).round() as u8
+        };
+        Rgba([blend(r, br), blend(g, bg), blend(b, bb), 255])
+    })
+}
+
+/// How the transparency of the input carries over to the dithered image
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Alpha {
+    /// Alpha is kept as it is
+    Keep,
+    /// Pixels with at least this alpha become opaque, the others transparent
+    Threshold(u8),
+    /// Alpha is dithered into a 1-bit mask with Floyd-Steinberg, so
+    /// soft edges and shadows fade out
+    Dither,
+}
+
+impl Alpha {
+    /// Looks up a mode by name, `keep`, `threshold` (at 128) or `dither`
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "keep" => Some(Alpha::Keep),
+            "threshold" => Some(Alpha::Threshold(128)),
+            "dither" => Some(Alpha::Dither),
+            _ => None,
+        }
+    }
+
+    /// Alpha channel of the output for `img`
+    ///
+    /// ## Returns
+    /// GrayImage buffer of alpha values, only 0 and 255 unless alpha is kept
+    pub fn channel(self, img: &RgbaImage) -> GrayImage {
+        match self {
+            Alpha::Keep => ImageBuffer::from_fn(img.width(), img.height(), |x, y| {
+                Luma([img.get_pixel(x, y)[3]])
+            }),
+            Alpha::Threshold(threshold) => {
+                ImageBuffer::from_fn(img.width(), img.height(), |x, y| {
+                    let opaque = img.get_pixel(x, y)[3] >= threshold;
+                    Luma([if opaque { 255 } else { 0 }])
+                })
+            }
+            Alpha::Dither => {
+                // Alpha is coverage, already linear
+                let coverage = ImageBuffer::from_fn(img.width(), img.height(), |x, y| {
+                    let a = img.get_pixel(x, y)[3];
+                    Rgba([a, a, a, 255])
+                });
+                ErrorDiffusion::new(DiffusionKernel::FLOYD_STEINBERG)
+                    .with_grayscale(Grayscale::Red)
+                    .with_linear(false)
+                    .dither(&coverage)
+            }
+        }
+    }
+
+    /// Adds the alpha channel of `img` to its dithered gray version
+    pub fn apply(self, img: &RgbaImage, dithered: &GrayImage) -> GrayAlphaImage {
+        let alpha = self.channel(img);
+        ImageBuffer::from_fn(dithered.width(), dithered.height(), |x, y| {
+            LumaA([dithered.get_pixel(x, y)[0], alpha.get_pixel(x, y)[0]])
+        })
+    }
+
+    /// Adds the alpha channel of `img` to its dithered color version
+    pub fn apply_rgb(self, img: &RgbaImage, dithered: &RgbImage) -> RgbaImage {
+        let alpha = self.channel(img);
+        ImageBuffer::from_fn(dithered.width(), dithered.height(), |x, y| {
+            let Rgb([r, g, b]) = *dithered.get_pixel(x, y);
+            Rgba([r, g, b, alpha.get_pixel(x, y)[0]])
+        })
+    }
+}
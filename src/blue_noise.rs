@@ -0,0 +1,196 @@
+use std::path::Path;
+
+use image::error::{ImageError, ParameterError, ParameterErrorKind};
+use image::ImageResult;
+
+use crate::{rng::Rng, ThresholdMap};
+
+/// Spread of the gaussian filter used to find clusters and voids, as in
+/// Ulichney's paper
+const SIGMA: f32 = 1.5;
+
+/// Share of pixels turned on in the initial random pattern
+const INITIAL_DENSITY: f32 = 0.1;
+
+impl ThresholdMap {
+    /// Generates a `size`x`size` blue-noise mask with the
+    /// [void-and-cluster](https://cv.ulichney.com/papers/1993-void-cluster.pdf)
+    /// algorithm. The same size and seed always give the same mask
+    ///
+    /// Generation is quadratic in the number of pixels, so masks bigger than
+    /// 64x64 are best generated once and cached, see
+    /// [`ThresholdMap::blue_noise_cached`]
+    ///
+    /// ## Parameters
+    /// - `size`: Side of the mask
+    /// - `seed`: Seed of the initial random pattern
+    /// ## Returns
+    /// None if `size` is 0
+    pub fn blue_noise(size: u32, seed: u64) -> Option<Self> {
+        if size == 0 {
+            return None;
+        }
+
+        let ranks = VoidAndCluster::new(size as usize, seed).ranks();
+        let count = ranks.len() as f32;
+        let values = ranks
+            .into_iter()
+            .map(|r| (r as f32 + 0.5) / count)
+            .collect();
+        Self::new(size, size, values)
+    }
+
+    /// Same as [`ThresholdMap::blue_noise`], but keeps the mask in `dir` and
+    /// only generates it if it isn't there yet
+    ///
+    /// ## Parameters
+    /// - `size`: Side of the mask
+    /// - `seed`: Seed of the initial random pattern
+    /// - `dir`: Cache folder, created if missing
+    /// ## Returns
+    /// The mask, or the error raised while reading or writing the cache. A
+    /// parameter error if `size` is 0
+    pub fn blue_noise_cached(size: u32, seed: u64, dir: impl AsRef<Path>) -> ImageResult<Self> {
+        if size == 0 {
+            return Err(ImageError::Parameter(ParameterError::from_kind(
+                ParameterErrorKind::Generic("blue noise size must be positive".into()),
+            )));
+        }
+
+        let path = dir
+            .as_ref()
+            .join(format!("blue_noise_{}_{}.png", size, seed));
+        if path.exists() {
+            return Self::load(&path);
+        }
+
+        std::fs::create_dir_all(dir)?;
+        // Size 0, the only way generation fails, is rejected above
+        let map = Self::blue_noise(size, seed).unwrap();
+        map.save(&path)?;
+        Ok(map)
+    }
+}
+
+/// State of the void-and-cluster algorithm on a toroidal `size`x`size` grid
+struct VoidAndCluster {
+    size: usize,
+    /// Gaussian weight of every toroidal offset
+    filter: Vec<f32>,
+    pattern: Vec<bool>,
+    /// Filtered pattern, high where ones cluster and low in voids
+    energy: Vec<f32>,
+}
+
+impl VoidAndCluster {
+    fn new(size: usize, seed: u64) -> Self {
+        let mut filter = vec![0.0; size * size];
+        for y in 0..size {
+            for x in 0..size {
+                // Shortest distance around the torus
+                let dx = x.min(size - x) as f32;
+                let dy = y.min(size - y) as f32;
+                filter[y * size + x] = (-(dx * dx + dy * dy) / (2.0 * SIGMA * SIGMA)).exp();
+            }
+        }
+
+        let mut state = Self {
+            size,
+            filter,
+            pattern: vec![false; size * size],
+            energy: vec![0.0; size * size],
+        };
+
+        let mut rng = Rng::new(seed);
+        let ones = ((size * size) as f32 * INITIAL_DENSITY).max(1.0) as usize;
+        let mut placed = 0;
+        while placed < ones {
+            let i = rng.below(size * size);
+            if !state.pattern[i] {
+                state.set(i, true);
+                placed += 1;
+            }
+        }
+
+        state
+    }
+
+    /// Flips the pixel at `i` and updates the energy of every pixel
+    fn set(&mut self, i: usize, value: bool) {
+        self.pattern[i] = value;
+        let sign = if value { 1.0 } else { -1.0 };
+        let (ix, iy) = (i % self.size, i / self.size);
+
+        for y in 0..self.size {
+            let fy = (y + self.size - iy) % self.size;
+            for x in 0..self.size {
+                let fx = (x + self.size - ix) % self.size;
+                self.energy[y * self.size + x] += sign * self.filter[fy * self.size + fx];
+            }
+        }
+    }
+
+    /// Index of the one with the highest energy
+    fn tightest_cluster(&self) -> usize {
+        self.extreme(true, |a, b| a > b)
+    }
+
+    /// Index of the zero with the lowest energy
+    fn largest_void(&self) -> usize {
+        self.extreme(false, |a, b| a < b)
+    }
+
+    fn extreme(&self, value: bool, better: impl Fn(f32, f32) -> bool) -> usize {
+        let mut best = None;
+        for (i, (&p, &e)) in self.pattern.iter().zip(&self.energy).enumerate() {
+            if p == value && best.is_none_or(|(_, b)| better(e, b)) {
+                best = Some((i, e));
+            }
+        }
+        best.map(|(i, _)| i).unwrap()
+    }
+
+    /// Runs the algorithm and returns the rank of every pixel
+    fn ranks(mut self) -> Vec<usize> {
+        let count = self.size * self.size;
+        let mut ranks = vec![0; count];
+
+        // Spread the initial pattern evenly by moving the tightest cluster
+        // into the largest void until it stops moving
+        if count > 1 {
+            loop {
+                let cluster = self.tightest_cluster();
+                self.set(cluster, false);
+                let void = self.largest_void();
+                self.set(void, true);
+                if void == cluster {
+                    break;
+                }
+            }
+        }
+
+        let initial = self.pattern.clone();
+        let initial_energy = self.energy.clone();
+        let ones = initial.iter().filter(|&&p| p).count();
+
+        // Rank the initial ones by removing the tightest cluster each time
+        for rank in (0..ones).rev() {
+            let cluster = self.tightest_cluster();
+            self.set(cluster, false);
+            ranks[cluster] = rank;
+        }
+
+        // Rank the remaining pixels by filling the largest void each time.
+        // The energy of zeros is the complement of the energy of ones, so
+        // this also covers the second half of the original algorithm
+        self.pattern = initial;
+        self.energy = initial_energy;
+        for rank in ones..count {
+            let void = self.largest_void();
+            self.set(void, true);
+            ranks[void] = rank;
+        }
+
+        ranks
+    }
+}
@@ -0,0 +1,144 @@
+use image::{GrayImage, ImageBuffer, Rgb, RgbImage, Rgba, RgbaImage};
+
+use crate::{Halftone, BLACK, WHITE};
+
+/// One bilevel image per ink, black where the ink is printed
+#[derive(Debug, Clone, PartialEq)]
+pub struct Separation {
+    pub cyan: GrayImage,
+    pub magenta: GrayImage,
+    pub yellow: GrayImage,
+    pub black: GrayImage,
+}
+
+impl Separation {
+    /// Simulates printing the four separations on top of each other on white
+    /// paper, with ideal inks
+    pub fn preview(&self) -> RgbImage {
+        let (w, h) = self.cyan.dimensions();
+        let ink = |img: &GrayImage, x: u32, y: u32| img.get_pixel(x, y).0[0] == 0;
+
+        ImageBuffer::from_fn(w, h, |x, y| {
+            if ink(&self.black, x, y) {
+                return Rgb([0, 0, 0]);
+            }
+            let channel = |img: &GrayImage| if ink(img, x, y) { 0 } else { 255 };
+            Rgb([
+                channel(&self.cyan),
+                channel(&self.magenta),
+                channel(&self.yellow),
+            ])
+        })
+    }
+}
+
+/// Separates an image into cyan, magenta, yellow and black, each halftoned on
+/// its own screen angle so the screens don't form a moiré
+///
+/// ```no_run
+/// use dithering::{CmykHalftone, Halftone};
+///
+/// let img = image::open("rei.jpeg").unwrap().to_rgba8();
+/// let separation = CmykHalftone::new(Halftone::new(85.0, 600.0)).separate(&img);
+/// separation.black.save("rei.k.png").unwrap();
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CmykHalftone {
+    screen: Halftone,
+    angles: [f32; 4],
+    gcr: f32,
+}
+
+impl CmykHalftone {
+    /// Classic screen angles for cyan, magenta, yellow and black
+    pub const DEFAULT_ANGLES: [f32; 4] = [15.0, 75.0, 0.0, 45.0];
+
+    /// Separates with full gray component replacement and the
+    /// [default angles](Self::DEFAULT_ANGLES)
+    ///
+    /// ## Parameters
+    /// - `screen`: Screen frequency, resolution and dot shape of every ink,
+    ///   its angle is ignored
+    pub fn new(screen: Halftone) -> Self {
+        Self {
+            screen,
+            angles: Self::DEFAULT_ANGLES,
+            gcr: 1.0,
+        }
+    }
+
+    /// Sets the screen angles of cyan, magenta, yellow and black, in degrees
+    pub fn with_angles(mut self, angles: [f32; 4]) -> Self {
+        self.angles = angles;
+        self
+    }
+
+    /// Sets how much of the gray component shared by cyan, magenta and yellow
+    /// is printed with black ink instead, between 0 and 1.
+    /// 0 prints every color with CMY only, 1 takes all the gray out of them
+    pub fn with_gcr(mut self, gcr: f32) -> Self {
+        self.gcr = gcr.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn screen(&self) -> Halftone {
+        self.screen
+    }
+
+    pub fn angles(&self) -> [f32; 4] {
+        self.angles
+    }
+
+    pub fn gcr(&self) -> f32 {
+        self.gcr
+    }
+
+    /// Converts an RGB pixel to the amount of each ink it needs, between 0 and 1
+    ///
+    /// ## Parameters
+    /// - `pixel`: Rgba pixel, alpha is ignored
+    /// ## Returns
+    /// Cyan, magenta, yellow and black coverage
+    pub fn to_cmyk(&self, pixel: &Rgba<u8>) -> [f32; 4] {
+        let [r, g, b, ..] = pixel.0;
+        let c = 1.0 - f32::from(r) / 255.0;
+        let m = 1.0 - f32::from(g) / 255.0;
+        let y = 1.0 - f32::from(b) / 255.0;
+
+        // Under color removal: black replaces the gray component, and the
+        // remaining inks are scaled to keep the same color
+        let k = self.gcr * c.min(m).min(y);
+        if k >= 1.0 {
+            return [0.0, 0.0, 0.0, 1.0];
+        }
+        let remove = |v: f32| (v - k) / (1.0 - k);
+        [remove(c), remove(m), remove(y), k]
+    }
+
+    /// Separates `img` into four halftoned inks
+    pub fn separate(&self, img: &RgbaImage) -> Separation {
+        let (w, h) = img.dimensions();
+        let inks: Vec<[f32; 4]> = img.pixels().map(|p| self.to_cmyk(p)).collect();
+
+        let channel = |i: usize| -> GrayImage {
+            let Some(map) = self.screen.with_angle(self.angles[i]).threshold_map(w, h) else {
+                return GrayImage::new(w, h);
+            };
+            ImageBuffer::from_fn(w, h, |x, y| {
+                let coverage = inks[(y * w + x) as usize][i];
+                if 1.0 - coverage > map.threshold(x, y) {
+                    WHITE
+                } else {
+                    BLACK
+                }
+            })
+        };
+
+        Separation {
+            cyan: channel(0),
+            magenta: channel(1),
+            yellow: channel(2),
+            black: channel(3),
+        }
+    }
+}
@@ -0,0 +1,837 @@
+use image::{GrayImage, RgbImage, RgbaImage};
+use std::borrow::Cow;
+
+use crate::target::{dither_gray, dither_palette, stored_gray, Target};
+use crate::{Ditherer, GrayLevels, Grayscale, Modulation, Palette, Threshold};
+
+/// A single entry of a [`DiffusionKernel`]: the pixel at (`dx`, `dy`) relative
+/// to the current one receives `weight / divisor` of the quantization error
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tap {
+    pub dx: i32,
+    pub dy: i32,
+    pub weight: f32,
+}
+
+impl Tap {
+    pub const fn new(dx: i32, dy: i32, weight: f32) -> Self {
+        Self { dx, dy, weight }
+    }
+}
+
+/// Right, down-left and down weights of [`DiffusionKernel::OSTROMOUKHOV`]
+/// for gray levels 0 to 127, with their sum, as published in the paper.
+/// Levels past 127 mirror them
+const OSTROMOUKHOV_COEFFICIENTS: [[u16; 4]; 128] = [
+    [13, 0, 5, 18],
+    [13, 0, 5, 18],
+    [21, 0, 10, 31],
+    [7, 0, 4, 11],
+    [8, 0, 5, 13],
+    [47, 3, 28, 78],
+    [23, 3, 13, 39],
+    [15, 3, 8, 26],
+    [22, 6, 11, 39],
+    [43, 15, 20, 78],
+    [7, 3, 3, 13],
+    [501, 224, 211, 936],
+    [249, 116, 103, 468],
+    [165, 80, 67, 312],
+    [123, 62, 49, 234],
+    [489, 256, 191, 936],
+    [81, 44, 31, 156],
+    [483, 272, 181, 936],
+    [60, 35, 22, 117],
+    [53, 32, 19, 104],
+    [237, 148, 83, 468],
+    [471, 304, 161, 936],
+    [3, 2, 1, 6],
+    [481, 314, 185, 980],
+    [354, 226, 155, 735],
+    [1389, 866, 685, 2940],
+    [227, 138, 125, 490],
+    [267, 158, 163, 588],
+    [327, 188, 220, 735],
+    [61, 34, 46, 141],
+    [627, 338, 505, 1470],
+    [1227, 638, 1075, 2940],
+    [20, 10, 19, 49],
+    [1937, 1000, 1767, 4704],
+    [977, 520, 855, 2352],
+    [657, 360, 551, 1568],
+    [71, 40, 57, 168],
+    [2005, 1160, 1539, 4704],
+    [337, 200, 247, 784],
+    [2039, 1240, 1425, 4704],
+    [257, 160, 171, 588],
+    [691, 440, 437, 1568],
+    [1045, 680, 627, 2352],
+    [301, 200, 171, 672],
+    [177, 120, 95, 392],
+    [2141, 1480, 1083, 4704],
+    [1079, 760, 513, 2352],
+    [725, 520, 323, 1568],
+    [137, 100, 57, 294],
+    [2209, 1640, 855, 4704],
+    [53, 40, 19, 112],
+    [2243, 1720, 741, 4704],
+    [565, 440, 171, 1176],
+    [759, 600, 209, 1568],
+    [1147, 920, 285, 2352],
+    [2311, 1880, 513, 4704],
+    [97, 80, 19, 196],
+    [335, 280, 57, 672],
+    [1181, 1000, 171, 2352],
+    [793, 680, 95, 1568],
+    [599, 520, 57, 1176],
+    [2413, 2120, 171, 4704],
+    [405, 360, 19, 784],
+    [2447, 2200, 57, 4704],
+    [11, 10, 0, 21],
+    [158, 151, 3, 312],
+    [178, 179, 7, 364],
+    [1030, 1091, 63, 2184],
+    [248, 277, 21, 546],
+    [318, 375, 35, 728],
+    [458, 571, 63, 1092],
+    [878, 1159, 147, 2184],
+    [5, 7, 1, 13],
+    [172, 181, 37, 390],
+    [97, 76, 22, 195],
+    [72, 41, 17, 130],
+    [119, 47, 29, 195],
+    [4, 1, 1, 6],
+    [4, 1, 1, 6],
+    [4, 1, 1, 6],
+    [4, 1, 1, 6],
+    [4, 1, 1, 6],
+    [4, 1, 1, 6],
+    [4, 1, 1, 6],
+    [4, 1, 1, 6],
+    [4, 1, 1, 6],
+    [65, 18, 17, 100],
+    [95, 29, 26, 150],
+    [185, 62, 53, 300],
+    [30, 11, 9, 50],
+    [35, 14, 11, 60],
+    [85, 37, 28, 150],
+    [55, 26, 19, 100],
+    [80, 41, 29, 150],
+    [155, 86, 59, 300],
+    [5, 3, 2, 10],
+    [5, 3, 2, 10],
+    [5, 3, 2, 10],
+    [5, 3, 2, 10],
+    [5, 3, 2, 10],
+    [5, 3, 2, 10],
+    [5, 3, 2, 10],
+    [305, 176, 119, 600],
+    [155, 86, 59, 300],
+    [105, 56, 39, 200],
+    [80, 41, 29, 150],
+    [65, 32, 23, 120],
+    [55, 26, 19, 100],
+    [335, 152, 113, 600],
+    [85, 37, 28, 150],
+    [115, 48, 37, 200],
+    [35, 14, 11, 60],
+    [355, 136, 109, 600],
+    [30, 11, 9, 50],
+    [365, 128, 107, 600],
+    [185, 62, 53, 300],
+    [25, 8, 7, 40],
+    [95, 29, 26, 150],
+    [385, 112, 103, 600],
+    [65, 18, 17, 100],
+    [395, 104, 101, 600],
+    [4, 1, 1, 6],
+    [4, 1, 1, 6],
+    [4, 1, 1, 6],
+    [4, 1, 1, 6],
+    [4, 1, 1, 6],
+    [4, 1, 1, 6],
+    [4, 1, 1, 6],
+];
+
+/// Weights of [`DiffusionKernel::OSTROMOUKHOV`] for every gray level, three
+/// per level, each level summing to 1
+const OSTROMOUKHOV_WEIGHTS: [f32; 256 * 3] = {
+    let mut weights = [0.0; 256 * 3];
+    let mut level = 0;
+    while level < 128 {
+        let [r, dl, d, sum] = OSTROMOUKHOV_COEFFICIENTS[level];
+        let row = [r, dl, d];
+        let mut c = 0;
+        while c < 3 {
+            let weight = row[c] as f32 / sum as f32;
+            weights[level * 3 + c] = weight;
+            weights[(255 - level) * 3 + c] = weight;
+            c += 1;
+        }
+        level += 1;
+    }
+    weights
+};
+
+/// Taps of [`DiffusionKernel::OSTROMOUKHOV`], weighted as at mid-gray
+const OSTROMOUKHOV_TAPS: [Tap; 3] = [
+    Tap::new(1, 0, OSTROMOUKHOV_WEIGHTS[128 * 3]),
+    Tap::new(-1, 1, OSTROMOUKHOV_WEIGHTS[128 * 3 + 1]),
+    Tap::new(0, 1, OSTROMOUKHOV_WEIGHTS[128 * 3 + 2]),
+];
+
+/// Describes how an error-diffusion algorithm spreads the quantization error
+/// of a pixel to its neighbours
+///
+/// Offsets are relative to the current pixel, with `x` growing to the right
+/// and `y` growing downwards
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiffusionKernel {
+    taps: Cow<'static, [Tap]>,
+    divisor: f32,
+    /// Weights of the taps for each gray level of the pixel, 0 to 255, one
+    /// row of `taps.len()` weights per level, if they vary
+    table: Option<&'static [f32]>,
+}
+
+impl DiffusionKernel {
+    /// Atkinson error diffusion, which only propagates 6/8 of the error
+    /// ```plaintext
+    ///       | PXL | 1/8 | 1/8 |
+    /// | 1/8 | 1/8 | 1/8 |
+    ///       | 1/8 |
+    /// ````
+    pub const ATKINSON: Self = Self::from_static(
+        &[
+            Tap::new(1, 0, 1.0),
+            Tap::new(2, 0, 1.0),
+            Tap::new(-1, 1, 1.0),
+            Tap::new(0, 1, 1.0),
+            Tap::new(1, 1, 1.0),
+            Tap::new(0, 2, 1.0),
+        ],
+        8.0,
+    );
+
+    /// Floyd-Steinberg error diffusion
+    /// ```plaintext
+    ///        |  PXL | 7/16 |
+    /// | 3/16 | 5/16 | 1/16 |
+    /// ````
+    pub const FLOYD_STEINBERG: Self = Self::from_static(
+        &[
+            Tap::new(1, 0, 7.0),
+            Tap::new(-1, 1, 3.0),
+            Tap::new(0, 1, 5.0),
+            Tap::new(1, 1, 1.0),
+        ],
+        16.0,
+    );
+
+    /// Jarvis-Judice-Ninke error diffusion
+    /// ```plaintext
+    ///        |      |  PXL | 7/48 | 5/48 |
+    /// | 3/48 | 5/48 | 7/48 | 5/48 | 3/48 |
+    /// | 1/48 | 3/48 | 5/48 | 3/48 | 1/48 |
+    /// ````
+    pub const JARVIS_JUDICE_NINKE: Self = Self::from_static(
+        &[
+            Tap::new(1, 0, 7.0),
+            Tap::new(2, 0, 5.0),
+            Tap::new(-2, 1, 3.0),
+            Tap::new(-1, 1, 5.0),
+            Tap::new(0, 1, 7.0),
+            Tap::new(1, 1, 5.0),
+            Tap::new(2, 1, 3.0),
+            Tap::new(-2, 2, 1.0),
+            Tap::new(-1, 2, 3.0),
+            Tap::new(0, 2, 5.0),
+            Tap::new(1, 2, 3.0),
+            Tap::new(2, 2, 1.0),
+        ],
+        48.0,
+    );
+
+    /// Stucki error diffusion
+    /// ```plaintext
+    ///        |      |  PXL | 8/42 | 4/42 |
+    /// | 2/42 | 4/42 | 8/42 | 4/42 | 2/42 |
+    /// | 1/42 | 2/42 | 4/42 | 2/42 | 1/42 |
+    /// ````
+    pub const STUCKI: Self = Self::from_static(
+        &[
+            Tap::new(1, 0, 8.0),
+            Tap::new(2, 0, 4.0),
+            Tap::new(-2, 1, 2.0),
+            Tap::new(-1, 1, 4.0),
+            Tap::new(0, 1, 8.0),
+            Tap::new(1, 1, 4.0),
+            Tap::new(2, 1, 2.0),
+            Tap::new(-2, 2, 1.0),
+            Tap::new(-1, 2, 2.0),
+            Tap::new(0, 2, 4.0),
+            Tap::new(1, 2, 2.0),
+            Tap::new(2, 2, 1.0),
+        ],
+        42.0,
+    );
+
+    /// Burkes error diffusion
+    /// ```plaintext
+    ///        |      |  PXL | 8/32 | 4/32 |
+    /// | 2/32 | 4/32 | 8/32 | 4/32 | 2/32 |
+    /// ````
+    pub const BURKES: Self = Self::from_static(
+        &[
+            Tap::new(1, 0, 8.0),
+            Tap::new(2, 0, 4.0),
+            Tap::new(-2, 1, 2.0),
+            Tap::new(-1, 1, 4.0),
+            Tap::new(0, 1, 8.0),
+            Tap::new(1, 1, 4.0),
+            Tap::new(2, 1, 2.0),
+        ],
+        32.0,
+    );
+
+    /// Sierra (three-row) error diffusion
+    /// ```plaintext
+    ///        |      |  PXL | 5/32 | 3/32 |
+    /// | 2/32 | 4/32 | 5/32 | 4/32 | 2/32 |
+    ///        | 2/32 | 3/32 | 2/32 |
+    /// ````
+    pub const SIERRA: Self = Self::from_static(
+        &[
+            Tap::new(1, 0, 5.0),
+            Tap::new(2, 0, 3.0),
+            Tap::new(-2, 1, 2.0),
+            Tap::new(-1, 1, 4.0),
+            Tap::new(0, 1, 5.0),
+            Tap::new(1, 1, 4.0),
+            Tap::new(2, 1, 2.0),
+            Tap::new(-1, 2, 2.0),
+            Tap::new(0, 2, 3.0),
+            Tap::new(1, 2, 2.0),
+        ],
+        32.0,
+    );
+
+    /// Two-row Sierra error diffusion
+    /// ```plaintext
+    ///        |      |  PXL | 4/16 | 3/16 |
+    /// | 1/16 | 2/16 | 3/16 | 2/16 | 1/16 |
+    /// ````
+    pub const TWO_ROW_SIERRA: Self = Self::from_static(
+        &[
+            Tap::new(1, 0, 4.0),
+            Tap::new(2, 0, 3.0),
+            Tap::new(-2, 1, 1.0),
+            Tap::new(-1, 1, 2.0),
+            Tap::new(0, 1, 3.0),
+            Tap::new(1, 1, 2.0),
+            Tap::new(2, 1, 1.0),
+        ],
+        16.0,
+    );
+
+    /// Sierra Lite error diffusion
+    /// ```plaintext
+    ///       | PXL | 2/4 |
+    /// | 1/4 | 1/4 |
+    /// ````
+    pub const SIERRA_LITE: Self = Self::from_static(
+        &[
+            Tap::new(1, 0, 2.0),
+            Tap::new(-1, 1, 1.0),
+            Tap::new(0, 1, 1.0),
+        ],
+        4.0,
+    );
+
+    /// Fan error diffusion
+    /// ```plaintext
+    ///        |      |  PXL | 7/16 |
+    /// | 1/16 | 3/16 | 5/16 |
+    /// ````
+    pub const FAN: Self = Self::from_static(
+        &[
+            Tap::new(1, 0, 7.0),
+            Tap::new(-2, 1, 1.0),
+            Tap::new(-1, 1, 3.0),
+            Tap::new(0, 1, 5.0),
+        ],
+        16.0,
+    );
+
+    /// Shiau-Fan error diffusion
+    /// ```plaintext
+    ///       |     | PXL | 4/8 |
+    /// | 1/8 | 1/8 | 2/8 |
+    /// ````
+    pub const SHIAU_FAN: Self = Self::from_static(
+        &[
+            Tap::new(1, 0, 4.0),
+            Tap::new(-2, 1, 1.0),
+            Tap::new(-1, 1, 1.0),
+            Tap::new(0, 1, 2.0),
+        ],
+        8.0,
+    );
+
+    /// Second Shiau-Fan variant, which spreads the error further to the left
+    /// ```plaintext
+    ///        |      |      |  PXL | 8/16 |
+    /// | 1/16 | 1/16 | 2/16 | 4/16 |
+    /// ````
+    pub const SHIAU_FAN_2: Self = Self::from_static(
+        &[
+            Tap::new(1, 0, 8.0),
+            Tap::new(-3, 1, 1.0),
+            Tap::new(-2, 1, 1.0),
+            Tap::new(-1, 1, 2.0),
+            Tap::new(0, 1, 4.0),
+        ],
+        16.0,
+    );
+
+    /// Ostromoukhov's variable-coefficient error diffusion, from "A Simple
+    /// and Efficient Error-Diffusion Algorithm" (SIGGRAPH 2001), where the
+    /// share of each tap depends on the gray level of the pixel. Meant to
+    /// run serpentine
+    /// ```plaintext
+    ///     | PXL |  r  |
+    /// | dl  |  d  |
+    /// ````
+    pub const OSTROMOUKHOV: Self = Self::variable(&OSTROMOUKHOV_TAPS, &OSTROMOUKHOV_WEIGHTS);
+
+    /// Every built-in kernel along with the name it is selected by
+    pub const NAMED: &'static [(&'static str, Self)] = &[
+        ("atkinson", Self::ATKINSON),
+        ("floyd", Self::FLOYD_STEINBERG),
+        ("jarvis", Self::JARVIS_JUDICE_NINKE),
+        ("stucki", Self::STUCKI),
+        ("burkes", Self::BURKES),
+        ("sierra", Self::SIERRA),
+        ("sierra2", Self::TWO_ROW_SIERRA),
+        ("sierra-lite", Self::SIERRA_LITE),
+        ("fan", Self::FAN),
+        ("shiau-fan", Self::SHIAU_FAN),
+        ("shiau-fan2", Self::SHIAU_FAN_2),
+        ("ostromoukhov", Self::OSTROMOUKHOV),
+    ];
+
+    /// Looks up a built-in kernel by the name listed in [`DiffusionKernel::NAMED`]
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::NAMED
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, kernel)| kernel.clone())
+    }
+
+    /// Creates a kernel from a list of taps and the divisor their weights are
+    /// relative to
+    pub fn new(taps: Vec<Tap>, divisor: f32) -> Self {
+        Self {
+            taps: Cow::Owned(taps),
+            divisor,
+            table: None,
+        }
+    }
+
+    /// Same as [`DiffusionKernel::new`], usable in constants
+    pub const fn from_static(taps: &'static [Tap], divisor: f32) -> Self {
+        Self {
+            taps: Cow::Borrowed(taps),
+            divisor,
+            table: None,
+        }
+    }
+
+    /// Creates a variable-coefficient kernel, where the weights of the taps
+    /// depend on the gray level of the pixel being diffused
+    ///
+    /// ## Parameters
+    /// - `taps`: Offsets of the taps, with their weights at mid-gray
+    /// - `table`: 256 rows of one weight per tap, for each gray level from
+    ///   0 to 255, as shares of the error
+    pub const fn variable(taps: &'static [Tap], table: &'static [f32]) -> Self {
+        assert!(table.len() == 256 * taps.len());
+        Self {
+            taps: Cow::Borrowed(taps),
+            divisor: 1.0,
+            table: Some(table),
+        }
+    }
+
+    pub fn taps(&self) -> &[Tap] {
+        &self.taps
+    }
+
+    pub fn divisor(&self) -> f32 {
+        self.divisor
+    }
+
+    /// Whether the weights depend on the gray level of the pixel, see
+    /// [`DiffusionKernel::variable`]
+    pub fn is_variable(&self) -> bool {
+        self.table.is_some()
+    }
+
+    /// Weights of the taps for a pixel of gray level `gray`, relative to the
+    /// divisor
+    pub fn weights(&self, gray: u8) -> Cow<'_, [f32]> {
+        match self.row(gray) {
+            Some(row) => Cow::Borrowed(row),
+            None => self.taps.iter().map(|tap| tap.weight).collect(),
+        }
+    }
+
+    /// Row of the table for `gray`, None if the weights don't vary
+    fn row(&self, gray: u8) -> Option<&[f32]> {
+        let n = self.taps.len();
+        self.table.map(|table| &table[usize::from(gray) * n..][..n])
+    }
+}
+
+/// Whether the pixel at (i + offx, j + offy) is inside a `w`x`h` image
+fn contains(w: u32, h: u32, i: usize, j: usize, offx: i32, offy: i32) -> bool {
+    let (x, y) = (i as i64 + i64::from(offx), j as i64 + i64::from(offy));
+    (0..i64::from(w)).contains(&x) && (0..i64::from(h)).contains(&y)
+}
+
+/// Checks the pixel at (i + offx, j + offy) on buffer.
+/// If it exists, increments its value by `value` and updates buffer in place
+///
+/// ## Parameters
+/// - buffer: Vec<Vec<f32>> of luminosities
+/// - i: Initial x
+/// - j: Initial y
+/// - offx: Offset x
+/// - offy: Offset y
+/// - value: Value to increment
+fn increment_buffer<const N: usize>(
+    buffer: &mut [Vec<[f32; N]>],
+    i: usize,
+    j: usize,
+    offx: i32,
+    offy: i32,
+    value: [f32; N],
+) {
+    let (x, y) = (i as i32 + offx, j as i32 + offy);
+
+    if x < 0 || x > (buffer.len() - 1) as i32 || y < 0 || y > (buffer[0].len() - 1) as i32 {
+        return;
+    }
+
+    for (channel, v) in buffer[x as usize][y as usize].iter_mut().zip(value) {
+        *channel += v;
+    }
+}
+
+/// Order in which [`ErrorDiffusion`] visits the pixels of each line
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ScanOrder {
+    /// Every line is scanned in the same direction
+    #[default]
+    Raster,
+    /// Lines alternate direction (boustrophedon), with the kernel mirrored on
+    /// the lines scanned backwards. Avoids the diagonal "worm" artifacts
+    /// raster scanning leaves in flat regions
+    Serpentine,
+}
+
+impl ScanOrder {
+    /// Looks up a scan order by name, `raster` or `serpentine`
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "raster" => Some(ScanOrder::Raster),
+            "serpentine" => Some(ScanOrder::Serpentine),
+            _ => None,
+        }
+    }
+}
+
+/// What [`ErrorDiffusion`] does with the error taps aim past the image edges
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EdgeMode {
+    /// That share of the error is dropped, so edge pixels diffuse less
+    #[default]
+    Drop,
+    /// That share goes to the taps that stay inside the image, so edge pixels
+    /// diffuse as much error as the others
+    Redistribute,
+}
+
+impl EdgeMode {
+    /// Looks up an edge mode by name, `drop` or `redistribute`
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "drop" => Some(EdgeMode::Drop),
+            "redistribute" => Some(EdgeMode::Redistribute),
+            _ => None,
+        }
+    }
+}
+
+/// Error-diffusion dithering driven by a [`DiffusionKernel`]
+///
+/// ```no_run
+/// use dithering::{DiffusionKernel, Ditherer, ErrorDiffusion};
+///
+/// let img = image::open("rei.jpeg").unwrap().to_rgba8();
+/// let dithered = ErrorDiffusion::new(DiffusionKernel::ATKINSON).dither(&img);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ErrorDiffusion {
+    kernel: DiffusionKernel,
+    scan: ScanOrder,
+    strength: f32,
+    clamp: Option<(f32, f32)>,
+    edges: EdgeMode,
+    modulation: Option<Modulation>,
+    threshold: Option<Threshold>,
+    grayscale: Grayscale,
+    linear: bool,
+}
+
+impl ErrorDiffusion {
+    pub fn new(kernel: DiffusionKernel) -> Self {
+        // Variable-coefficient kernels are designed for serpentine scanning
+        let scan = if kernel.is_variable() {
+            ScanOrder::Serpentine
+        } else {
+            ScanOrder::default()
+        };
+        Self {
+            kernel,
+            scan,
+            strength: 1.0,
+            clamp: None,
+            edges: EdgeMode::default(),
+            modulation: None,
+            threshold: None,
+            grayscale: Grayscale::default(),
+            linear: true,
+        }
+    }
+
+    /// Sets the order pixels are visited in, [`ScanOrder::Raster`] by
+    /// default, or [`ScanOrder::Serpentine`] for variable-coefficient kernels
+    pub fn with_scan(mut self, scan: ScanOrder) -> Self {
+        self.scan = scan;
+        self
+    }
+
+    /// Sets the share of the error passed on to the neighbours, from 0 for
+    /// plain thresholding to 1 for the whole error, the default
+    pub fn with_strength(mut self, strength: f32) -> Self {
+        self.strength = strength.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Sets the range pixel values are clamped to once their neighbours'
+    /// error is added, on the scale where black is 0 and white 1. Keeps
+    /// error from piling up in areas the output can't follow, such as
+    /// saturated colors. Not clamped if None, the default
+    ///
+    /// ## Returns
+    /// None if a bound is NaN or the minimum is above the maximum
+    pub fn with_clamp(mut self, clamp: Option<(f32, f32)>) -> Option<Self> {
+        if let Some((min, max)) = clamp {
+            if min.is_nan() || max.is_nan() || min > max {
+                return None;
+            }
+        }
+        self.clamp = clamp;
+        Some(self)
+    }
+
+    /// Sets what happens to error aimed past the image edges,
+    /// [`EdgeMode::Drop`] by default
+    pub fn with_edges(mut self, edges: EdgeMode) -> Self {
+        self.edges = edges;
+        self
+    }
+
+    /// Sets how the threshold moves from pixel to pixel, to break up
+    /// textures in the mid-tones. Fixed if None, the default
+    pub fn with_modulation(mut self, modulation: Option<Modulation>) -> Self {
+        self.modulation = modulation;
+        self
+    }
+
+    /// Sets where gray output switches to the next level before the error
+    /// is diffused, halfway between levels if None, the default
+    pub fn with_threshold(mut self, threshold: Option<Threshold>) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    /// Sets how pixels are turned into gray values for black and white
+    /// output, [`Grayscale::Rec709`] by default
+    pub fn with_grayscale(mut self, grayscale: Grayscale) -> Self {
+        self.grayscale = grayscale;
+        self
+    }
+
+    /// Sets whether error is diffused in linear light, on by default. Off,
+    /// error is diffused on the gamma-encoded sRGB values, which makes the
+    /// output darker than the input
+    pub fn with_linear(mut self, linear: bool) -> Self {
+        self.linear = linear;
+        self
+    }
+
+    pub fn kernel(&self) -> &DiffusionKernel {
+        &self.kernel
+    }
+
+    pub fn scan(&self) -> ScanOrder {
+        self.scan
+    }
+
+    pub fn strength(&self) -> f32 {
+        self.strength
+    }
+
+    pub fn clamp(&self) -> Option<(f32, f32)> {
+        self.clamp
+    }
+
+    pub fn edges(&self) -> EdgeMode {
+        self.edges
+    }
+
+    pub fn modulation(&self) -> Option<&Modulation> {
+        self.modulation.as_ref()
+    }
+
+    pub fn threshold(&self) -> Option<Threshold> {
+        self.threshold
+    }
+
+    pub fn grayscale(&self) -> Grayscale {
+        self.grayscale
+    }
+
+    pub fn linear(&self) -> bool {
+        self.linear
+    }
+}
+
+impl ErrorDiffusion {
+    /// Runs the kernel over `values`, given in row-major order, and returns
+    /// the index of the target value chosen for each pixel
+    fn diffuse<const N: usize>(
+        &self,
+        w: u32,
+        h: u32,
+        values: &[[f32; N]],
+        target: &dyn Target<N>,
+    ) -> Vec<usize> {
+        let mut indices = vec![0; values.len()];
+        let mut buffer: Vec<Vec<[f32; N]>> = vec![vec![[0.0; N]; h as usize]; w as usize];
+
+        // Fill buffer
+        for i in 0..w as usize {
+            for j in 0..h as usize {
+                buffer[i][j] = values[j * w as usize + i];
+            }
+        }
+
+        let thresholds = self
+            .modulation
+            .as_ref()
+            .map(|modulation| modulation.thresholds(w, h, values, self.linear));
+
+        let divisor = self.kernel.divisor();
+        let total: f32 = self.kernel.taps().iter().map(|tap| tap.weight).sum();
+
+        // Kernels are laid out for row-major scanning: rows top to bottom,
+        // each row left to right (or right to left when reversed)
+        for y in 0..h {
+            let reverse = self.scan == ScanOrder::Serpentine && y % 2 == 1;
+
+            for step in 0..w {
+                let x = if reverse { w - 1 - step } else { step };
+                let i = x as usize;
+                let j = y as usize;
+
+                let old_pxl = match self.clamp {
+                    Some((min, max)) => buffer[i][j].map(|v| v.clamp(min, max)),
+                    None => buffer[i][j],
+                };
+                let index = match &thresholds {
+                    Some(thresholds) => target.ordered(old_pxl, thresholds[j * w as usize + i]),
+                    None => target.nearest(old_pxl),
+                };
+                let new_pxl = target.value(index);
+                let error: [f32; N] =
+                    std::array::from_fn(|c| (old_pxl[c] - new_pxl[c]) * self.strength);
+
+                // Variable kernels pick their weights by the gray level of
+                // the original pixel
+                let row = if self.kernel.is_variable() {
+                    let gray = stored_gray(values[j * w as usize + i], self.linear);
+                    self.kernel
+                        .row((gray.clamp(0.0, 1.0) * 255.0).round() as u8)
+                } else {
+                    None
+                };
+                let weight = |k: usize, tap: &Tap| row.map_or(tap.weight, |row| row[k]);
+                let total = row.map_or(total, |row| row.iter().sum());
+
+                let mirror = |dx: i32| if reverse { -dx } else { dx };
+                let scale = match self.edges {
+                    EdgeMode::Drop => 1.0,
+                    EdgeMode::Redistribute => {
+                        let inside: f32 = self
+                            .kernel
+                            .taps()
+                            .iter()
+                            .enumerate()
+                            .filter(|(_, tap)| contains(w, h, i, j, mirror(tap.dx), tap.dy))
+                            .map(|(k, tap)| weight(k, tap))
+                            .sum();
+                        if inside > 0.0 {
+                            total / inside
+                        } else {
+                            1.0
+                        }
+                    }
+                };
+
+                for (k, tap) in self.kernel.taps().iter().enumerate() {
+                    let weight = weight(k, tap) * scale;
+                    let value = error.map(|e| e * weight / divisor);
+                    increment_buffer(&mut buffer, i, j, mirror(tap.dx), tap.dy, value);
+                }
+
+                indices[j * w as usize + i] = index;
+            }
+        }
+
+        indices
+    }
+}
+
+impl Ditherer for ErrorDiffusion {
+    fn dither_levels(&self, img: &RgbaImage, levels: &GrayLevels) -> GrayImage {
+        let (w, h) = img.dimensions();
+        dither_gray(
+            img,
+            levels,
+            self.grayscale,
+            self.linear,
+            self.threshold,
+            |values, target| self.diffuse(w, h, values, target),
+        )
+    }
+
+    fn dither_palette(&self, img: &RgbaImage, palette: &Palette) -> RgbImage {
+        let (w, h) = img.dimensions();
+        dither_palette(img, palette, self.linear, |values, target| {
+            self.diffuse(w, h, values, target)
+        })
+    }
+}
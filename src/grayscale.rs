@@ -0,0 +1,92 @@
+use image::Rgba;
+
+use crate::color::{srgb_to_lab, srgb_to_linear};
+
+/// How a color pixel is turned into the gray value that gets dithered
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Grayscale {
+    /// Rec. 601 luma weights, as used by analog TV and JPEG
+    Rec601,
+    /// Rec. 709 weights, the same as [`luminosity`](crate::luminosity) and
+    /// [`luminance`](crate::luminance)
+    #[default]
+    Rec709,
+    /// CIE L*, perceived lightness. In linear light it is turned back into
+    /// the luminance it encodes, which is the same as [`Grayscale::Rec709`]
+    CieLightness,
+    /// HSL lightness, halfway between the strongest and weakest channel
+    HslLightness,
+    /// Mean of the three channels
+    Average,
+    /// Red channel only
+    Red,
+    /// Green channel only
+    Green,
+    /// Blue channel only
+    Blue,
+    /// Red, green and blue weights, see [`Grayscale::custom`]
+    Custom([f32; 3]),
+}
+
+impl Grayscale {
+    /// Looks up a method by name, `rec601`, `rec709`, `cie-lightness`, `hsl`,
+    /// `average`, `red`, `green` or `blue`
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "rec601" => Some(Grayscale::Rec601),
+            "rec709" => Some(Grayscale::Rec709),
+            "cie-lightness" => Some(Grayscale::CieLightness),
+            "hsl" => Some(Grayscale::HslLightness),
+            "average" => Some(Grayscale::Average),
+            "red" => Some(Grayscale::Red),
+            "green" => Some(Grayscale::Green),
+            "blue" => Some(Grayscale::Blue),
+            _ => None,
+        }
+    }
+
+    /// Weighs the red, green and blue channels, with weights scaled to add
+    /// up to 1
+    ///
+    /// ## Returns
+    /// None if a weight is negative or not finite, or if they are all 0
+    pub fn custom(weights: [f32; 3]) -> Option<Self> {
+        let sum: f32 = weights.iter().sum();
+        if weights.iter().any(|w| !w.is_finite() || *w < 0.0) || sum <= 0.0 {
+            return None;
+        }
+        Some(Grayscale::Custom(weights.map(|w| w / sum)))
+    }
+
+    /// Gray value of `pixel` between 0 and 1
+    ///
+    /// ## Parameters
+    /// - `linear`: Decode the channels from sRGB first, so the value is in
+    ///   linear light
+    pub fn gray(&self, pixel: &Rgba<u8>, linear: bool) -> f32 {
+        let rgb = [pixel[0], pixel[1], pixel[2]];
+        if *self == Grayscale::CieLightness && !linear {
+            return srgb_to_lab(rgb.map(|c| f32::from(c) / 255.0))[0] / 100.0;
+        }
+
+        // Undecoded channels are weighed before scaling, as `luminosity` does
+        let (channels, scale) = if linear {
+            (rgb.map(|c| srgb_to_linear(f32::from(c) / 255.0)), 1.0)
+        } else {
+            (rgb.map(f32::from), 255.0)
+        };
+        let [r, g, b] = channels;
+
+        let gray = match self {
+            Grayscale::Rec601 => 0.299 * r + 0.587 * g + 0.114 * b,
+            Grayscale::Rec709 | Grayscale::CieLightness => 0.2126 * r + 0.7152 * g + 0.0722 * b,
+            Grayscale::HslLightness => (r.max(g).max(b) + r.min(g).min(b)) / 2.0,
+            Grayscale::Average => (r + g + b) / 3.0,
+            Grayscale::Red => r,
+            Grayscale::Green => g,
+            Grayscale::Blue => b,
+            Grayscale::Custom([wr, wg, wb]) => wr * r + wg * g + wb * b,
+        };
+        gray / scale
+    }
+}
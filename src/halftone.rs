@@ -0,0 +1,218 @@
+use image::{GrayImage, RgbImage, RgbaImage};
+
+use crate::{Ditherer, GrayLevels, Grayscale, Ordered, Palette, ThresholdMap};
+
+/// Shape dots grow in, as the spot function of a halftone screen
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DotShape {
+    #[default]
+    Round,
+    /// Dots stretched along the screen angle, which join into chains in the
+    /// mid-tones
+    Elliptical,
+    Square,
+    /// Lines along the screen angle
+    Line,
+}
+
+impl DotShape {
+    /// Looks up a dot shape by name: `round`, `elliptical`, `square` or `line`
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "round" => Some(DotShape::Round),
+            "elliptical" => Some(DotShape::Elliptical),
+            "square" => Some(DotShape::Square),
+            "line" => Some(DotShape::Line),
+            _ => None,
+        }
+    }
+
+    /// Spot function of the shape at (x, y), both between -1 and 1 with the
+    /// dot centered at (0, 0). Points with higher values turn black first
+    fn spot(self, x: f32, y: f32) -> f32 {
+        let shape = match self {
+            DotShape::Round => -(x * x + y * y),
+            DotShape::Elliptical => -(x * x + (y / 0.6) * (y / 0.6)),
+            DotShape::Square => -x.abs().max(y.abs()),
+            DotShape::Line => -y.abs(),
+        };
+
+        // Squares and lines grow a whole ring or row at once otherwise,
+        // start with the pixels closest to the center
+        shape - 1e-3 * (x * x + y * y)
+    }
+}
+
+/// Amplitude-modulated halftoning: black dots on a regular, rotated screen,
+/// growing with the darkness of the image
+///
+/// The image is assumed to be at `dpi` pixels per inch, so a screen of `lpi`
+/// lines per inch has cells of `dpi / lpi` pixels
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Halftone {
+    lpi: f32,
+    dpi: f32,
+    angle: f32,
+    shape: DotShape,
+    grayscale: Grayscale,
+    linear: bool,
+}
+
+impl Halftone {
+    /// Creates a screen of round dots at 45°
+    ///
+    /// ## Parameters
+    /// - `lpi`: Screen frequency, in lines per inch
+    /// - `dpi`: Resolution of the image, in pixels per inch
+    pub fn new(lpi: f32, dpi: f32) -> Self {
+        Self {
+            lpi,
+            dpi,
+            angle: 45.0,
+            shape: DotShape::default(),
+            grayscale: Grayscale::default(),
+            linear: true,
+        }
+    }
+
+    /// Sets the screen angle, in degrees
+    pub fn with_angle(mut self, angle: f32) -> Self {
+        self.angle = angle;
+        self
+    }
+
+    pub fn with_shape(mut self, shape: DotShape) -> Self {
+        self.shape = shape;
+        self
+    }
+
+    /// Sets how pixels are turned into gray values, [`Grayscale::Rec709`]
+    /// by default
+    pub fn with_grayscale(mut self, grayscale: Grayscale) -> Self {
+        self.grayscale = grayscale;
+        self
+    }
+
+    /// Sets whether dots are sized from the luminance in linear light, on
+    /// by default, see [`Ordered::with_linear`]
+    pub fn with_linear(mut self, linear: bool) -> Self {
+        self.linear = linear;
+        self
+    }
+
+    pub fn lpi(&self) -> f32 {
+        self.lpi
+    }
+
+    pub fn dpi(&self) -> f32 {
+        self.dpi
+    }
+
+    pub fn angle(&self) -> f32 {
+        self.angle
+    }
+
+    pub fn shape(&self) -> DotShape {
+        self.shape
+    }
+
+    pub fn grayscale(&self) -> Grayscale {
+        self.grayscale
+    }
+
+    pub fn linear(&self) -> bool {
+        self.linear
+    }
+
+    /// Lays the screen over a `w`x`h` image
+    ///
+    /// The pixels of each cell are ranked by the spot function, so a dot
+    /// covers as much of its cell as the image is dark there, one pixel per
+    /// level, whatever its shape or angle. Cells cut by the image edges are
+    /// ranked with their pixels past the edges, so they keep the shape and
+    /// position of their dot
+    ///
+    /// ## Returns
+    /// None if the image is empty
+    pub fn threshold_map(&self, w: u32, h: u32) -> Option<ThresholdMap> {
+        let cell = self.dpi / self.lpi;
+        let (sin, cos) = self.angle.to_radians().sin_cos();
+
+        // Cell and spot value of the pixel at (x, y), sampled at its center,
+        // which may lie past the image edges
+        let locate = |x: i64, y: i64| {
+            let (px, py) = (x as f32 + 0.5, y as f32 + 0.5);
+            let u = (px * cos + py * sin) / cell;
+            let v = (py * cos - px * sin) / cell;
+
+            let spot = self
+                .shape
+                .spot(2.0 * u.rem_euclid(1.0) - 1.0, 2.0 * v.rem_euclid(1.0) - 1.0);
+            ((u.floor() as i64, v.floor() as i64), spot)
+        };
+
+        let mut cells: Vec<(i64, i64)> = (0..h as i64)
+            .flat_map(|y| (0..w as i64).map(move |x| (x, y)))
+            .map(|(x, y)| locate(x, y).0)
+            .collect();
+        cells.sort_unstable();
+        cells.dedup();
+
+        let mut values = vec![0.0; w as usize * h as usize];
+        for (u, v) in cells {
+            // Bounds of the cell on the image, from its corners
+            let corners = [(0, 0), (1, 0), (0, 1), (1, 1)].map(|(du, dv)| {
+                let (u, v) = ((u + du) as f32 * cell, (v + dv) as f32 * cell);
+                (u * cos - v * sin, u * sin + v * cos)
+            });
+            let bound = |coordinate: fn(&(f32, f32)) -> f32| {
+                let values = corners.iter().map(coordinate);
+                let min = values.clone().fold(f32::INFINITY, f32::min);
+                let max = values.fold(f32::NEG_INFINITY, f32::max);
+                (min - 0.5).floor() as i64..=(max - 0.5).ceil() as i64
+            };
+
+            // Every pixel of the cell in row-major order, turning black last
+            // first, which get the lowest thresholds
+            let mut pixels: Vec<(f32, i64, i64)> = bound(|c| c.1)
+                .flat_map(|y| bound(|c| c.0).map(move |x| (x, y)))
+                .filter_map(|(x, y)| {
+                    let (at, spot) = locate(x, y);
+                    (at == (u, v)).then_some((spot, y, x))
+                })
+                .collect();
+            pixels.sort_by(|a, b| a.0.total_cmp(&b.0).then((a.1, a.2).cmp(&(b.1, b.2))));
+
+            for (rank, &(_, y, x)) in pixels.iter().enumerate() {
+                if (0..w as i64).contains(&x) && (0..h as i64).contains(&y) {
+                    values[(y * w as i64 + x) as usize] = (rank as f32 + 0.5) / pixels.len() as f32;
+                }
+            }
+        }
+
+        ThresholdMap::new(w, h, values)
+    }
+}
+
+impl Ditherer for Halftone {
+    fn dither_levels(&self, img: &RgbaImage, levels: &GrayLevels) -> GrayImage {
+        let (w, h) = img.dimensions();
+        match self.threshold_map(w, h) {
+            Some(map) => Ordered::new(map)
+                .with_grayscale(self.grayscale)
+                .with_linear(self.linear)
+                .dither_levels(img, levels),
+            None => GrayImage::new(w, h),
+        }
+    }
+
+    fn dither_palette(&self, img: &RgbaImage, palette: &Palette) -> RgbImage {
+        let (w, h) = img.dimensions();
+        match self.threshold_map(w, h) {
+            Some(map) => Ordered::new(map)
+                .with_linear(self.linear)
+                .dither_palette(img, palette),
+            None => RgbImage::new(w, h),
+        }
+    }
+}
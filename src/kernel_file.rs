@@ -0,0 +1,208 @@
+//! Loading [`DiffusionKernel`]s from text files
+//!
+//! The file lays the kernel out the same way as the diagrams in the kernel
+//! docs: one row per line, cells separated by whitespace, `X` (or `*`) marking
+//! the current pixel and `.` marking cells that receive nothing. An optional
+//! `divisor = N` line gives the value weights are relative to, it defaults to
+//! the sum of the weights. Lines starting with `#` are comments.
+//!
+//! ```plaintext
+//! # Floyd-Steinberg
+//! divisor = 16
+//! . X 7
+//! 3 5 1
+//! ```
+
+use std::{error::Error, fmt, fs, io, path::Path, str::FromStr};
+
+use crate::{DiffusionKernel, Tap};
+
+/// Reasons a kernel file can be rejected
+#[derive(Debug)]
+pub enum KernelError {
+    /// The file could not be read
+    Io(io::Error),
+    /// A line could not be parsed
+    Syntax { line: usize, message: String },
+    /// No cell is marked as the current pixel
+    MissingMarker,
+    /// More than one cell is marked as the current pixel
+    DuplicateMarker { line: usize },
+    /// A row has a different number of cells than the first one
+    RaggedRow { line: usize },
+    /// The divisor is zero, negative or not finite
+    InvalidDivisor(f32),
+    /// A weight is negative or not finite
+    InvalidWeight { dx: i32, dy: i32, weight: f32 },
+    /// A weight points at a pixel that is already processed
+    BackwardTap { dx: i32, dy: i32 },
+    /// The kernel would diffuse more error than the pixel had
+    WeightSum(f32),
+    /// The kernel has no non-zero weights
+    Empty,
+}
+
+impl fmt::Display for KernelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KernelError::Io(e) => write!(f, "failed to read kernel: {}", e),
+            KernelError::Syntax { line, message } => write!(f, "line {}: {}", line, message),
+            KernelError::MissingMarker => write!(f, "no cell is marked as the current pixel (X)"),
+            KernelError::DuplicateMarker { line } => {
+                write!(
+                    f,
+                    "line {}: current pixel (X) is marked more than once",
+                    line
+                )
+            }
+            KernelError::RaggedRow { line } => {
+                write!(f, "line {}: row has a different number of cells", line)
+            }
+            KernelError::InvalidDivisor(d) => write!(f, "divisor must be positive, got {}", d),
+            KernelError::InvalidWeight { dx, dy, weight } => write!(
+                f,
+                "weight at offset ({}, {}) must be positive, got {}",
+                dx, dy, weight
+            ),
+            KernelError::BackwardTap { dx, dy } => write!(
+                f,
+                "offset ({}, {}) points at an already processed pixel",
+                dx, dy
+            ),
+            KernelError::WeightSum(sum) => {
+                write!(f, "weights sum to {} of the error, more than 1", sum)
+            }
+            KernelError::Empty => write!(f, "kernel has no weights"),
+        }
+    }
+}
+
+impl Error for KernelError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            KernelError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for KernelError {
+    fn from(e: io::Error) -> Self {
+        KernelError::Io(e)
+    }
+}
+
+impl DiffusionKernel {
+    /// Reads and validates a kernel file, see the [module docs](self) for the format
+    ///
+    /// ## Parameters
+    /// - `path`: Path of the kernel file
+    /// ## Returns
+    /// The kernel, or the first problem found in the file
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, KernelError> {
+        fs::read_to_string(path)?.parse()
+    }
+
+    /// Checks that the kernel only diffuses forward, to pixels that are not
+    /// processed yet, and never spreads more than the whole error
+    pub fn validate(&self) -> Result<(), KernelError> {
+        if self.taps().iter().all(|tap| tap.weight == 0.0) {
+            return Err(KernelError::Empty);
+        }
+
+        let divisor = self.divisor();
+        if !divisor.is_finite() || divisor <= 0.0 {
+            return Err(KernelError::InvalidDivisor(divisor));
+        }
+
+        let mut sum = 0.0;
+        for &Tap { dx, dy, weight } in self.taps() {
+            if !weight.is_finite() || weight < 0.0 {
+                return Err(KernelError::InvalidWeight { dx, dy, weight });
+            }
+            if dy < 0 || (dy == 0 && dx <= 0) {
+                return Err(KernelError::BackwardTap { dx, dy });
+            }
+            sum += weight;
+        }
+
+        // Allow for rounding in fractional weights
+        if sum / divisor > 1.0 + 1e-6 {
+            return Err(KernelError::WeightSum(sum / divisor));
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for DiffusionKernel {
+    type Err = KernelError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut divisor = None;
+        let mut rows: Vec<(usize, Vec<&str>)> = Vec::new();
+
+        for (i, line) in s.lines().enumerate() {
+            let line_no = i + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if let Some(value) = line.strip_prefix("divisor") {
+                let value = value.trim_start().strip_prefix('=').unwrap_or(value).trim();
+                let parsed = value.parse::<f32>().map_err(|_| KernelError::Syntax {
+                    line: line_no,
+                    message: format!("invalid divisor {}", value),
+                })?;
+                divisor = Some(parsed);
+                continue;
+            }
+
+            let cells: Vec<&str> = line.split_whitespace().collect();
+            if rows
+                .first()
+                .is_some_and(|(_, first)| first.len() != cells.len())
+            {
+                return Err(KernelError::RaggedRow { line: line_no });
+            }
+            rows.push((line_no, cells));
+        }
+
+        // Find the current pixel first, offsets are relative to it
+        let mut marker = None;
+        for (row, (line_no, cells)) in rows.iter().enumerate() {
+            for (col, cell) in cells.iter().enumerate() {
+                if matches!(*cell, "X" | "x" | "*") {
+                    if marker.is_some() {
+                        return Err(KernelError::DuplicateMarker { line: *line_no });
+                    }
+                    marker = Some((col as i32, row as i32));
+                }
+            }
+        }
+        let (mx, my) = marker.ok_or(KernelError::MissingMarker)?;
+
+        let mut taps = Vec::new();
+        for (row, (line_no, cells)) in rows.iter().enumerate() {
+            for (col, cell) in cells.iter().enumerate() {
+                if matches!(*cell, "X" | "x" | "*" | ".") {
+                    continue;
+                }
+                let weight = cell.parse::<f32>().map_err(|_| KernelError::Syntax {
+                    line: *line_no,
+                    message: format!("invalid weight {}", cell),
+                })?;
+                if weight != 0.0 {
+                    taps.push(Tap::new(col as i32 - mx, row as i32 - my, weight));
+                }
+            }
+        }
+
+        let divisor = divisor.unwrap_or_else(|| taps.iter().map(|tap| tap.weight).sum());
+        let kernel = DiffusionKernel::new(taps, divisor);
+        kernel.validate()?;
+
+        Ok(kernel)
+    }
+}
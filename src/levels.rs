@@ -0,0 +1,141 @@
+use std::borrow::Cow;
+
+use crate::color::srgb_to_linear;
+use crate::target::Target;
+
+/// Gray values a [`Ditherer`](crate::Ditherer) may output, such as the four
+/// grays of a 2bpp display
+///
+/// Levels are kept sorted from darkest to lightest, without duplicates
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GrayLevels {
+    levels: Cow<'static, [u8]>,
+}
+
+impl Default for GrayLevels {
+    fn default() -> Self {
+        Self::BLACK_WHITE
+    }
+}
+
+impl GrayLevels {
+    /// Pure black and white, 1-bit output
+    pub const BLACK_WHITE: GrayLevels = GrayLevels {
+        levels: Cow::Borrowed(&[0, 255]),
+    };
+
+    /// ## Returns
+    /// None if there are fewer than two distinct levels
+    pub fn new(mut levels: Vec<u8>) -> Option<Self> {
+        levels.sort_unstable();
+        levels.dedup();
+        (levels.len() >= 2).then_some(Self {
+            levels: Cow::Owned(levels),
+        })
+    }
+
+    /// `n` levels evenly spaced from black to white, as sent to the display
+    ///
+    /// ## Returns
+    /// None if `n` isn't between 2 and 256
+    pub fn even(n: usize) -> Option<Self> {
+        if !(2..=256).contains(&n) {
+            return None;
+        }
+        Self::new(
+            (0..n)
+                .map(|i| (i as f32 * 255.0 / (n - 1) as f32).round() as u8)
+                .collect(),
+        )
+    }
+
+    pub fn levels(&self) -> &[u8] {
+        &self.levels
+    }
+
+    /// Gray value of the level at `index`
+    pub(crate) fn level(&self, index: usize) -> u8 {
+        self.levels[index]
+    }
+
+    /// The levels as values between 0 and 1, decoded from sRGB if `linear`
+    ///
+    /// ## Parameters
+    /// - `cut`: Value in the same space where output switches from black to
+    ///   white, halfway between each pair of levels if None
+    pub(crate) fn target(&self, linear: bool, cut: Option<f32>) -> LevelTarget {
+        let values = self
+            .levels
+            .iter()
+            .map(|&level| {
+                let v = f32::from(level) / 255.0;
+                if linear {
+                    srgb_to_linear(v)
+                } else {
+                    v
+                }
+            })
+            .collect::<Vec<_>>();
+
+        // Kept as a fraction of the range, so every pair of levels is split
+        // at the same place
+        let (first, last) = (values[0], values[values.len() - 1]);
+        let split = cut.map(|cut| ((cut - first) / (last - first)).clamp(0.0, 1.0));
+        LevelTarget { values, split }
+    }
+}
+
+/// Sorted gray levels in the space dithering works in
+pub(crate) struct LevelTarget {
+    values: Vec<f32>,
+    /// Fraction of the gap between two levels where output switches to the
+    /// upper one, if it isn't halfway
+    split: Option<f32>,
+}
+
+impl LevelTarget {
+    /// Index of the upper level if `value` is past `fraction` of the way
+    /// from the level below it to the one above, else of the lower level
+    fn split(&self, value: f32, fraction: f32) -> usize {
+        let last = self.values.len() - 1;
+        let upper = self.values[1..last]
+            .iter()
+            .take_while(|&&level| value > level)
+            .count()
+            + 1;
+        let (low, high) = (self.values[upper - 1], self.values[upper]);
+        if (value - low) / (high - low) > fraction {
+            upper
+        } else {
+            upper - 1
+        }
+    }
+}
+
+impl Target<1> for LevelTarget {
+    fn nearest(&self, [value]: [f32; 1]) -> usize {
+        if let Some(fraction) = self.split {
+            return self.split(value, fraction);
+        }
+        // Ties go to the darker level
+        self.values
+            .windows(2)
+            .take_while(|pair| value > (pair[0] + pair[1]) / 2.0)
+            .count()
+    }
+
+    fn value(&self, index: usize) -> [f32; 1] {
+        [self.values[index]]
+    }
+
+    /// Compares the threshold to the position of `value` between the two
+    /// levels around it, so each level is covered in proportion however
+    /// uneven their spacing. A split away from halfway moves the threshold
+    /// along with it
+    fn ordered(&self, [value]: [f32; 1], threshold: f32) -> usize {
+        match self.split {
+            Some(fraction) => self.split(value, (fraction + threshold - 0.5).clamp(0.0, 1.0)),
+            None => self.split(value, threshold),
+        }
+    }
+}
@@ -0,0 +1,129 @@
+//! Dithering algorithms that turn an RGBA image into a 1-bit grayscale image,
+//! an image of a few [`GrayLevels`], or an image using only the colors of a
+//! [`Palette`].
+//!
+//! Every algorithm implements [`Ditherer`], so callers can pick one at runtime
+//! and treat them uniformly:
+//!
+//! ```no_run
+//! use dithering::{DiffusionKernel, Ditherer, ErrorDiffusion};
+//!
+//! let img = image::open("rei.jpeg").unwrap().to_rgba8();
+//! let dithered = ErrorDiffusion::new(DiffusionKernel::FLOYD_STEINBERG).dither(&img);
+//! dithered.save("rei.floyd.jpeg").unwrap();
+//! ```
+
+use image::{GrayImage, Luma, RgbImage, Rgba, RgbaImage};
+
+use color::srgb_to_linear;
+
+mod alpha;
+mod blue_noise;
+mod cmyk;
+pub mod color;
+mod diffusion;
+mod grayscale;
+mod halftone;
+pub mod kernel_file;
+mod levels;
+mod metric;
+mod modulation;
+mod ordered;
+mod palette;
+pub mod palette_file;
+mod presets;
+mod quantize;
+mod riemersma;
+mod rng;
+mod target;
+mod threshold;
+
+pub use alpha::{composite, Alpha};
+pub use cmyk::{CmykHalftone, Separation};
+pub use diffusion::{DiffusionKernel, EdgeMode, ErrorDiffusion, ScanOrder, Tap};
+pub use grayscale::Grayscale;
+pub use halftone::{DotShape, Halftone};
+pub use kernel_file::KernelError;
+pub use levels::GrayLevels;
+pub use metric::Metric;
+pub use modulation::Modulation;
+pub use ordered::{Ordered, ThresholdMap};
+pub use palette::{Palette, PaletteError};
+pub use palette_file::{PaletteFileError, PaletteFormat};
+pub use quantize::Quantizer;
+pub use riemersma::Riemersma;
+pub use threshold::{Threshold, Thresholding};
+
+pub(crate) const WHITE: Luma<u8> = Luma([255]);
+pub(crate) const BLACK: Luma<u8> = Luma([0]);
+
+/// An algorithm that reduces an RGBA image to black and white pixels, a few
+/// gray levels or the colors of a palette
+pub trait Ditherer {
+    /// Dithers `img` into a new grayscale image of the same dimensions
+    ///
+    /// ## Parameters
+    /// - `img`: RgbaImage
+    /// ## Returns
+    /// GrayImage buffer containing only black and white pixels
+    fn dither(&self, img: &RgbaImage) -> GrayImage {
+        self.dither_levels(img, &GrayLevels::BLACK_WHITE)
+    }
+
+    /// Dithers `img` into a new grayscale image of the same dimensions,
+    /// carrying error to the nearest of `levels`
+    ///
+    /// ## Parameters
+    /// - `img`: RgbaImage
+    /// - `levels`: Grays the output may use
+    /// ## Returns
+    /// GrayImage buffer containing only gray values of `levels`
+    fn dither_levels(&self, img: &RgbaImage, levels: &GrayLevels) -> GrayImage;
+
+    /// Dithers `img` and writes the result back into it.
+    /// Color channels are replaced with the dithered value, alpha is kept as is
+    ///
+    /// ## Parameters
+    /// - `img`: RgbaImage to update
+    fn dither_in_place(&self, img: &mut RgbaImage) {
+        let dithered = self.dither(img);
+        for (pxl, Luma([v])) in img.pixels_mut().zip(dithered.pixels()) {
+            pxl.0[..3].fill(*v);
+        }
+    }
+
+    /// Dithers `img` to the colors of `palette`, carrying error on each
+    /// color channel separately
+    ///
+    /// ## Parameters
+    /// - `img`: RgbaImage
+    /// - `palette`: Colors the output may use
+    /// ## Returns
+    /// RgbImage buffer containing only colors of the palette
+    fn dither_palette(&self, img: &RgbaImage, palette: &Palette) -> RgbImage;
+}
+
+/// Calculates [Relative Luminance](https://en.wikipedia.org/wiki/Relative_luminance)
+/// of an Rgba pixel, which returns a Grayscale value we can work on
+///
+/// ## Parameters
+/// - `pixel`: Rgba pixel
+/// ## Returns
+/// f32 luminosity
+pub fn luminosity(pixel: &Rgba<u8>) -> f32 {
+    let [r, g, b, ..] = pixel.0;
+    0.2126 * f32::from(r) + 0.7152 * f32::from(g) + 0.0722 * f32::from(b)
+}
+
+/// Calculates the relative luminance of an Rgba pixel in linear light.
+/// Unlike [`luminosity`], channels are decoded from sRGB before being
+/// weighted, so a pixel at 0.5 reflects half as much light as white
+///
+/// ## Parameters
+/// - `pixel`: Rgba pixel
+/// ## Returns
+/// f32 luminance between 0 and 1
+pub fn luminance(pixel: &Rgba<u8>) -> f32 {
+    let [r, g, b] = [pixel[0], pixel[1], pixel[2]].map(|c| srgb_to_linear(f32::from(c) / 255.0));
+    0.2126 * r + 0.7152 * g + 0.0722 * b
+}
@@ -1,181 +1,798 @@
-use image::io::Reader as ImageReader;
-use image::{GrayImage, ImageBuffer, Luma, Rgba, RgbaImage};
-use std::{fs, path::Path, vec};
-
-const WHITE: Luma<u8> = Luma([255]);
-const BLACK: Luma<u8> = Luma([0]);
-
-/// Calculates [Relative Luminance](https://en.wikipedia.org/wiki/Relative_luminance)
-/// of an Rgba pixel, which returns a Grayscale value we can work on
-///
-/// ## Parameters
-/// - `pixel`: Rgba pixel
-/// ## Returns
-/// f32 luminosity
-fn luminosity(pixel: &Rgba<u8>) -> f32 {
-    let [r, g, b, ..] = pixel.0;
-    0.2126 * f32::from(r) + 0.7152 * f32::from(g) + 0.0722 * f32::from(b)
+use dithering::{
+    composite, Alpha, CmykHalftone, DiffusionKernel, Ditherer, DotShape, EdgeMode, ErrorDiffusion,
+    GrayLevels, Grayscale, Halftone, Metric, Modulation, Ordered, Palette, PaletteFormat,
+    Quantizer, Riemersma, ScanOrder, Threshold, ThresholdMap, Thresholding,
+};
+use image::{io::Reader as ImageReader, DynamicImage, Rgb, RgbaImage};
+use std::{fs, path::Path, process};
+
+/// Algorithms that run when none is given on the command line
+const DEFAULT_ALGORITHMS: [&str; 2] = ["atkinson", "floyd"];
+
+/// An algorithm picked on the command line, turned into a [`Ditherer`] once
+/// every option is parsed
+enum Algorithm {
+    Diffusion(DiffusionKernel),
+    Riemersma,
+    Threshold,
+    Bayer,
+    BlueNoise,
+    ThresholdMap(ThresholdMap),
+    Halftone,
+    Cmyk,
 }
 
-/// Checks the pixel at (i + offx, j + offy) on buffer.
-/// If it exists, increments its value by `value` and updates buffer in place
-///
-/// ## Parameters
-/// - buffer: Vec<Vec<f32>> of luminosities
-/// - i: Initial x
-/// - j: Initial y
-/// - offx: Offset x
-/// - offy: Offset y
-/// - value: Value to increment
-fn increment_buffer(
-    buffer: &mut Vec<Vec<f32>>,
-    i: usize,
-    j: usize,
-    offx: i32,
-    offy: i32,
-    value: f32,
-) {
-    let (x, y) = (i as i32 + offx, j as i32 + offy);
-
-    if x < 0 || x > (buffer.len() - 1) as i32 || y < 0 || y > (buffer[0].len() - 1) as i32 {
-        return;
+impl Algorithm {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "riemersma" => Some(Algorithm::Riemersma),
+            "threshold" => Some(Algorithm::Threshold),
+            "bayer" => Some(Algorithm::Bayer),
+            "blue-noise" => Some(Algorithm::BlueNoise),
+            "halftone" => Some(Algorithm::Halftone),
+            "cmyk" => Some(Algorithm::Cmyk),
+            _ => DiffusionKernel::from_name(name).map(Algorithm::Diffusion),
+        }
     }
 
-    buffer[x as usize][y as usize] += value;
-}
+    fn names() -> Vec<&'static str> {
+        let mut names: Vec<&str> = DiffusionKernel::NAMED
+            .iter()
+            .map(|(name, _)| *name)
+            .collect();
+        names.extend([
+            "riemersma",
+            "threshold",
+            "bayer",
+            "blue-noise",
+            "halftone",
+            "cmyk",
+        ]);
+        names
+    }
 
-/// Uses Atkinson's algorithm to dither the image
-///
-/// Atkinson error diffusin is as follows
-/// ```plaintext
-///       | PXL | 1/8 | 1/8 |
-/// | 1/8 | 1/8 | 1/8 |
-///       | 1/8 |
-/// ````
-///
-/// ## Parameters
-/// - `img``: RgbaImage
-/// ## Returns
-/// GrayImage buffer
-fn atkinson(img: &RgbaImage) -> GrayImage {
-    let (w, h) = img.dimensions();
-    let mut new_img: GrayImage = ImageBuffer::new(w, h);
-    let mut buffer: Vec<Vec<f32>> = vec![vec![0.0; h as usize]; w as usize];
-
-    // Fill buffer
-    for i in 0..w {
-        for j in 0..h {
-            buffer[i as usize][j as usize] = luminosity(img.get_pixel(i, j)) / 255.0;
+    /// Side of the threshold matrix when `--matrix-size` isn't given
+    fn default_matrix_size(&self) -> u32 {
+        match self {
+            Algorithm::BlueNoise => 64,
+            _ => 8,
         }
     }
 
-    for x in 0..w {
-        for y in 0..h {
-            let i = x as usize;
-            let j = y as usize;
+    /// Runs the algorithm on `img`
+    ///
+    /// ## Returns
+    /// Every output image, along with the suffix added to its file name if
+    /// the algorithm has more than one output
+    fn render(
+        &self,
+        args: &Args,
+        img: &RgbaImage,
+    ) -> Result<Vec<(Option<&'static str>, DynamicImage)>, String> {
+        let matrix_size = args
+            .matrix_size
+            .unwrap_or_else(|| self.default_matrix_size());
 
-            let old_pxl = buffer[i][j];
-            let new_pxl = if old_pxl > 0.5 { 1.0 } else { 0.0 };
-            let error = old_pxl - new_pxl;
+        let ditherer: Box<dyn Ditherer> = match self {
+            Algorithm::Diffusion(kernel) => {
+                let diffusion = ErrorDiffusion::new(kernel.clone());
+                let scan = args.scan.unwrap_or(diffusion.scan());
+                Box::new(
+                    diffusion
+                        .with_scan(scan)
+                        .with_strength(args.strength)
+                        .with_clamp(args.clamp)
+                        // The range is validated while parsing
+                        .unwrap()
+                        .with_edges(args.edges)
+                        .with_modulation(args.modulation.clone())
+                        .with_threshold(args.threshold)
+                        .with_grayscale(args.grayscale)
+                        .with_linear(args.linear),
+                )
+            }
+            Algorithm::Riemersma => Box::new(
+                Riemersma::default()
+                    .with_threshold(args.threshold)
+                    .with_grayscale(args.grayscale)
+                    .with_linear(args.linear),
+            ),
+            Algorithm::Threshold => Box::new(
+                Thresholding::new()
+                    .with_threshold(args.threshold)
+                    .with_grayscale(args.grayscale)
+                    .with_linear(args.linear),
+            ),
+            Algorithm::Bayer => Box::new(
+                Ordered::bayer(matrix_size)
+                    .ok_or(format!("bayer matrix size {} is too large", matrix_size))?
+                    .with_grayscale(args.grayscale)
+                    .with_linear(args.linear),
+            ),
+            Algorithm::BlueNoise => {
+                let map = match &args.cache_dir {
+                    Some(dir) => ThresholdMap::blue_noise_cached(matrix_size, args.seed, dir)
+                        .map_err(|e| format!("failed to cache blue noise in {}: {}", dir, e))?,
+                    None => ThresholdMap::blue_noise(matrix_size, args.seed)
+                        .ok_or("blue noise matrix size must be positive")?,
+                };
+                Box::new(
+                    Ordered::new(map)
+                        .with_grayscale(args.grayscale)
+                        .with_linear(args.linear),
+                )
+            }
+            Algorithm::ThresholdMap(map) => Box::new(
+                Ordered::new(map.clone())
+                    .with_grayscale(args.grayscale)
+                    .with_linear(args.linear),
+            ),
+            Algorithm::Halftone => Box::new(
+                Halftone::new(args.lpi, args.dpi)
+                    .with_angle(args.angle)
+                    .with_shape(args.dot)
+                    .with_grayscale(args.grayscale)
+                    .with_linear(args.linear),
+            ),
+            Algorithm::Cmyk => {
+                let separation =
+                    CmykHalftone::new(Halftone::new(args.lpi, args.dpi).with_shape(args.dot))
+                        .with_angles(args.cmyk_angles)
+                        .with_gcr(args.gcr)
+                        .separate(img);
 
-            increment_buffer(&mut buffer, i, j, -1, 1, error * 1.0 / 8.0);
-            increment_buffer(&mut buffer, i, j, 0, 1, error * 1.0 / 8.0);
-            increment_buffer(&mut buffer, i, j, 0, 2, error * 1.0 / 8.0);
-            increment_buffer(&mut buffer, i, j, 1, 1, error * 1.0 / 8.0);
-            increment_buffer(&mut buffer, i, j, 0, 1, error * 1.0 / 8.0);
-            increment_buffer(&mut buffer, i, j, 0, 2, error * 1.0 / 8.0);
+                let mut outputs = Vec::new();
+                if args.preview {
+                    outputs.push((Some("preview"), separation.preview().into()));
+                }
+                outputs.extend([
+                    (Some("c"), separation.cyan.into()),
+                    (Some("m"), separation.magenta.into()),
+                    (Some("y"), separation.yellow.into()),
+                    (Some("k"), separation.black.into()),
+                ]);
+                return Ok(outputs);
+            }
+        };
 
-            let pxl = if new_pxl == 1.0 { WHITE } else { BLACK };
-            new_img.put_pixel(x, y, pxl);
-        }
+        let output = match (&args.palette, args.alpha) {
+            (Some(palette), None) => DynamicImage::ImageRgb8(ditherer.dither_palette(img, palette)),
+            (Some(palette), Some(alpha)) => DynamicImage::ImageRgba8(
+                alpha.apply_rgb(img, &ditherer.dither_palette(img, palette)),
+            ),
+            (None, None) => DynamicImage::ImageLuma8(ditherer.dither_levels(img, &args.levels)),
+            (None, Some(alpha)) => DynamicImage::ImageLumaA8(
+                alpha.apply(img, &ditherer.dither_levels(img, &args.levels)),
+            ),
+        };
+        Ok(vec![(None, output)])
     }
+}
 
-    new_img
+struct Args {
+    file_path: String,
+    /// Output suffix of every algorithm to run
+    algorithms: Vec<(String, Algorithm)>,
+    /// Diffusion scan order, if not the one of the kernel
+    scan: Option<ScanOrder>,
+    /// Share of the error diffused, between 0 and 1
+    strength: f32,
+    clamp: Option<(f32, f32)>,
+    edges: EdgeMode,
+    modulation: Option<Modulation>,
+    matrix_size: Option<u32>,
+    seed: u64,
+    cache_dir: Option<String>,
+    lpi: f32,
+    dpi: f32,
+    angle: f32,
+    dot: DotShape,
+    cmyk_angles: [f32; 4],
+    gcr: f32,
+    preview: bool,
+    /// Color transparent pixels are blended over when alpha isn't kept
+    background: Rgb<u8>,
+    alpha: Option<Alpha>,
+    /// Gray values of the output without a palette
+    levels: GrayLevels,
+    /// Where gray output switches to the next level, if not halfway
+    threshold: Option<Threshold>,
+    grayscale: Grayscale,
+    /// Whether to dither in linear light rather than on sRGB values
+    linear: bool,
+    palette: Option<Palette>,
+    /// Quantizer and number of colors of a palette taken from the image
+    quantize: Option<(Quantizer, usize)>,
+    metric: Option<Metric>,
+    /// Where to export the palette to
+    save_palette: Option<String>,
 }
 
-/// Uses Floyd-Steinberg algorithm to dither the image
-///
-/// Floyd-Steinberg error diffusin is as follows
-/// ```plaintext
-///        |  PXL | 7/16 |
-/// | 3/16 | 5/16 | 1/16 |
-/// ````
-///
-/// ## Parameters
-/// - `img``: RgbaImage
-/// ## Returns
-/// GrayImage buffer
-fn floyd_steinberg(img: &RgbaImage) -> GrayImage {
-    let (w, h) = img.dimensions();
-    let mut new_img: GrayImage = ImageBuffer::new(w, h);
-    let mut buffer: Vec<Vec<f32>> = vec![vec![0.0; h as usize]; w as usize];
-
-    // Fill buffer
-    for i in 0..w {
-        for j in 0..h {
-            buffer[i as usize][j as usize] = luminosity(img.get_pixel(i, j)) / 255.0;
-        }
+fn usage() -> String {
+    let default_algorithms = format!(
+        "Algorithm to run, can be repeated (default: {})",
+        DEFAULT_ALGORITHMS.join(", ")
+    );
+    let options = [
+        ("-a, --algorithm <name>", default_algorithms.as_str()),
+        (
+            "-k, --kernel <path>",
+            "Diffusion kernel file to run, can be repeated",
+        ),
+        (
+            "-t, --threshold-map <path>",
+            "Image to tile as an ordered dithering threshold map",
+        ),
+        (
+            "-s, --scan <order>",
+            "Diffusion scan order, raster or serpentine (default: raster, serpentine for ostromoukhov)",
+        ),
+        (
+            "--strength <percent>",
+            "Share of the error diffused, 0 to 100 (default: 100)",
+        ),
+        (
+            "--clamp <min,max>",
+            "Range diffused values are clamped to, black at 0 and white at 1",
+        ),
+        (
+            "--edges <mode>",
+            "Error past the image edges, drop or redistribute (default: drop)",
+        ),
+        (
+            "--modulation <kind>",
+            "Diffusion threshold modulation, white, blue or zhou-fang",
+        ),
+        (
+            "--modulation-amplitude <n>",
+            "Share of the gap the threshold moves by, 0 to 100 (default: 50)",
+        ),
+        (
+            "--matrix-size <n>",
+            "Side of the bayer (default: 8) or blue-noise (default: 64) matrix, and of the --modulation blue mask",
+        ),
+        ("--seed <n>", "Seed of randomized algorithms (default: 0)"),
+        (
+            "--cache-dir <path>",
+            "Folder to keep generated blue noise masks in",
+        ),
+        ("--lpi <n>", "Halftone screen frequency (default: 60)"),
+        (
+            "--dpi <n>",
+            "Resolution of the image for halftones (default: 300)",
+        ),
+        ("--angle <degrees>", "Halftone screen angle (default: 45)"),
+        (
+            "--dot <shape>",
+            "Halftone dot, round, elliptical, square or line (default: round)",
+        ),
+        (
+            "--cmyk-angles <c,m,y,k>",
+            "CMYK screen angles (default: 15,75,0,45)",
+        ),
+        (
+            "--gcr <amount>",
+            "Share of the CMY gray replaced by black, 0 to 1 (default: 1)",
+        ),
+        ("--preview", "Also save a simulated CMYK print"),
+        (
+            "--background <color>",
+            "Color transparent pixels are blended over (default: #ffffff)",
+        ),
+        (
+            "--alpha <mode>",
+            "Keep transparency, keep, dither, threshold or a 0-255 threshold",
+        ),
+        (
+            "--levels <n|list>",
+            "Gray levels, a number evenly spaced or 0-255 values like 0,90,180,255 (default: 2)",
+        ),
+        (
+            "--threshold <value>",
+            "Gray 0-255 where output turns white, or otsu, mean or median (default: halfway)",
+        ),
+        (
+            "--grayscale <method>",
+            "Gray conversion, rec601, rec709, cie-lightness (with --no-linear), hsl, average, red, green, blue or r,g,b weights (default: rec709)",
+        ),
+        (
+            "--no-linear",
+            "Dither sRGB values as they are instead of in linear light",
+        ),
+        (
+            "--palette <palette>",
+            "Dither to a palette: a name, a palette file or #rrggbb,... colors",
+        ),
+        (
+            "--quantize <method>",
+            "Take the palette from the image, median-cut, octree, kmeans or wu",
+        ),
+        (
+            "--colors <n>",
+            "Size of the palette taken from the image (default: 16)",
+        ),
+        (
+            "--metric <name>",
+            "Color distance, rgb, redmean, cie76, ciede2000 or oklab (default: rgb)",
+        ),
+        (
+            "--save-palette <path>",
+            "Export the palette, as .gpl, .act, .aco, .pal, .txt or .hex",
+        ),
+    ];
+
+    let mut usage = String::from("Usage: ./dithering [options] /path/to/image\n\nOptions:\n");
+    for (option, description) in options {
+        usage += &format!("  {:<28}{}\n", option, description);
     }
+    usage += &format!("\nAlgorithms: {}", Algorithm::names().join(", "));
+    let palettes: Vec<&str> = Palette::NAMED.iter().map(|(name, _)| *name).collect();
+    usage += &format!("\nPalettes: {}", palettes.join(", "));
+    usage
+}
 
-    for x in 0..w {
-        for y in 0..h {
-            let i = x as usize;
-            let j = y as usize;
+/// Parses command line arguments, without the program name
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Args, String> {
+    let mut file_path = None;
+    let mut algorithms = Vec::new();
+    let mut scan = None;
+    let mut strength = None;
+    let mut clamp = None;
+    let mut edges = None;
+    let mut modulation = None;
+    let mut amplitude = None;
+    let mut matrix_size = None;
+    let mut seed = 0;
+    let mut cache_dir = None;
+    let mut lpi = 60.0;
+    let mut dpi = 300.0;
+    let mut angle = 45.0;
+    let mut dot = DotShape::default();
+    let mut cmyk_angles = CmykHalftone::DEFAULT_ANGLES;
+    let mut gcr = 1.0;
+    let mut preview = false;
+    let mut background = None;
+    let mut alpha = None;
+    let mut levels = None;
+    let mut threshold = None;
+    let mut grayscale = None;
+    let mut linear = true;
+    let mut palette = None;
+    let mut quantizer = None;
+    let mut colors = None;
+    let mut metric = None;
+    let mut save_palette = None;
 
-            let old_pxl = buffer[i][j];
-            let new_pxl = if old_pxl > 0.5 { 1.0 } else { 0.0 };
-            let error = old_pxl - new_pxl;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-a" | "--algorithm" => {
+                let name = args.next().ok_or(format!("missing value for {}", arg))?;
+                let algorithm =
+                    Algorithm::from_name(&name).ok_or(format!("unknown algorithm {}", name))?;
+                algorithms.push((name, algorithm));
+            }
+            "-k" | "--kernel" => {
+                let path = args.next().ok_or(format!("missing value for {}", arg))?;
+                let kernel = DiffusionKernel::load(&path)
+                    .map_err(|e| format!("invalid kernel {}: {}", path, e))?;
+                let name = Path::new(&path)
+                    .file_stem()
+                    .map_or("kernel".into(), |stem| stem.to_string_lossy().into_owned());
+                algorithms.push((name, Algorithm::Diffusion(kernel)));
+            }
+            "-t" | "--threshold-map" => {
+                let path = args.next().ok_or(format!("missing value for {}", arg))?;
+                let map = ThresholdMap::load(&path)
+                    .map_err(|e| format!("invalid threshold map {}: {}", path, e))?;
+                let name = Path::new(&path)
+                    .file_stem()
+                    .map_or("map".into(), |stem| stem.to_string_lossy().into_owned());
+                algorithms.push((name, Algorithm::ThresholdMap(map)));
+            }
+            "-s" | "--scan" => {
+                let name = args.next().ok_or(format!("missing value for {}", arg))?;
+                scan = Some(
+                    ScanOrder::from_name(&name).ok_or(format!("unknown scan order {}", name))?,
+                );
+            }
+            "--strength" => {
+                let value = args.next().ok_or(format!("missing value for {}", arg))?;
+                let percent: f32 = value
+                    .parse()
+                    .ok()
+                    .filter(|percent| (0.0..=100.0).contains(percent))
+                    .ok_or(format!("strength must be between 0 and 100, got {}", value))?;
+                strength = Some(percent / 100.0);
+            }
+            "--clamp" => {
+                let value = args.next().ok_or(format!("missing value for {}", arg))?;
+                let bounds: Vec<f32> = value
+                    .split(',')
+                    .map(|bound| bound.trim().parse())
+                    .collect::<Result<_, _>>()
+                    .map_err(|_| format!("invalid clamp range {}", value))?;
+                match bounds[..] {
+                    [min, max] if min < max => clamp = Some((min, max)),
+                    _ => return Err(format!("expected a min,max range, got {}", value)),
+                }
+            }
+            "--edges" => {
+                let name = args.next().ok_or(format!("missing value for {}", arg))?;
+                edges =
+                    Some(EdgeMode::from_name(&name).ok_or(format!("unknown edge mode {}", name))?);
+            }
+            "--modulation" => {
+                let name = args.next().ok_or(format!("missing value for {}", arg))?;
+                if !["white", "blue", "zhou-fang"].contains(&name.as_str()) {
+                    return Err(format!("unknown modulation {}", name));
+                }
+                modulation = Some(name);
+            }
+            "--modulation-amplitude" => {
+                let value = args.next().ok_or(format!("missing value for {}", arg))?;
+                let percent: f32 = value
+                    .parse()
+                    .ok()
+                    .filter(|percent| (0.0..=100.0).contains(percent))
+                    .ok_or(format!(
+                        "amplitude must be between 0 and 100, got {}",
+                        value
+                    ))?;
+                amplitude = Some(percent / 100.0);
+            }
+            "--matrix-size" => {
+                let value = args.next().ok_or(format!("missing value for {}", arg))?;
+                let size: u32 = value
+                    .parse()
+                    .map_err(|_| format!("invalid matrix size {}", value))?;
+                matrix_size = Some(size);
+            }
+            "--seed" => {
+                let value = args.next().ok_or(format!("missing value for {}", arg))?;
+                seed = value
+                    .parse()
+                    .map_err(|_| format!("invalid seed {}", value))?;
+            }
+            "--cache-dir" => {
+                cache_dir = Some(args.next().ok_or(format!("missing value for {}", arg))?);
+            }
+            "--lpi" | "--dpi" | "--angle" => {
+                let value = args.next().ok_or(format!("missing value for {}", arg))?;
+                let number: f32 = value
+                    .parse()
+                    .ok()
+                    .filter(|n: &f32| n.is_finite())
+                    .ok_or(format!("invalid value {} for {}", value, arg))?;
+                match arg.as_str() {
+                    "--angle" => angle = number,
+                    _ if number <= 0.0 => return Err(format!("{} must be positive", arg)),
+                    "--lpi" => lpi = number,
+                    _ => dpi = number,
+                }
+            }
+            "--dot" => {
+                let name = args.next().ok_or(format!("missing value for {}", arg))?;
+                dot = DotShape::from_name(&name).ok_or(format!("unknown dot shape {}", name))?;
+            }
+            "--cmyk-angles" => {
+                let value = args.next().ok_or(format!("missing value for {}", arg))?;
+                let angles: Vec<f32> = value
+                    .split(',')
+                    .map(|angle| angle.trim().parse())
+                    .collect::<Result<_, _>>()
+                    .map_err(|_| format!("invalid angles {}", value))?;
+                cmyk_angles = angles
+                    .try_into()
+                    .map_err(|_| format!("expected four angles, got {}", value))?;
+            }
+            "--gcr" => {
+                let value = args.next().ok_or(format!("missing value for {}", arg))?;
+                gcr = value
+                    .parse()
+                    .ok()
+                    .filter(|gcr| (0.0..=1.0).contains(gcr))
+                    .ok_or(format!("gcr must be between 0 and 1, got {}", value))?;
+            }
+            "--preview" => preview = true,
+            "--background" => {
+                let value = args.next().ok_or(format!("missing value for {}", arg))?;
+                let color = Palette::from_hex(&value)
+                    .ok()
+                    .filter(|palette| palette.colors().len() == 1)
+                    .ok_or(format!("invalid background color {}", value))?;
+                background = Some(color.colors()[0]);
+            }
+            "--alpha" => {
+                let value = args.next().ok_or(format!("missing value for {}", arg))?;
+                alpha = Some(
+                    Alpha::from_name(&value)
+                        .or_else(|| value.parse().ok().map(Alpha::Threshold))
+                        .ok_or(format!("unknown alpha mode {}", value))?,
+                );
+            }
+            "--levels" => {
+                let value = args.next().ok_or(format!("missing value for {}", arg))?;
+                levels = Some(if value.contains(',') {
+                    let grays: Vec<u8> = value
+                        .split(',')
+                        .map(|gray| gray.trim().parse())
+                        .collect::<Result<_, _>>()
+                        .map_err(|_| format!("invalid gray levels {}", value))?;
+                    GrayLevels::new(grays).ok_or("at least two distinct gray levels are needed")?
+                } else {
+                    value
+                        .parse()
+                        .ok()
+                        .and_then(GrayLevels::even)
+                        .ok_or(format!("levels must be between 2 and 256, got {}", value))?
+                });
+            }
+            "--threshold" => {
+                let value = args.next().ok_or(format!("missing value for {}", arg))?;
+                threshold = Some(
+                    Threshold::from_name(&value)
+                        .or_else(|| value.parse().ok().map(Threshold::Fixed))
+                        .ok_or(format!("invalid threshold {}", value))?,
+                );
+            }
+            "--grayscale" => {
+                let value = args.next().ok_or(format!("missing value for {}", arg))?;
+                grayscale = Some(match Grayscale::from_name(&value) {
+                    Some(grayscale) => grayscale,
+                    None => {
+                        let weights: Vec<f32> = value
+                            .split(',')
+                            .map(|weight| weight.trim().parse())
+                            .collect::<Result<_, _>>()
+                            .map_err(|_| format!("unknown grayscale method {}", value))?;
+                        weights
+                            .try_into()
+                            .ok()
+                            .and_then(Grayscale::custom)
+                            .ok_or(format!("invalid grayscale weights {}", value))?
+                    }
+                });
+            }
+            "--no-linear" => linear = false,
+            "--palette" => {
+                let value = args.next().ok_or(format!("missing value for {}", arg))?;
+                palette = Some(if let Some(palette) = Palette::from_name(&value) {
+                    palette
+                } else if Path::new(&value).is_file() {
+                    Palette::load(&value)
+                        .map_err(|e| format!("invalid palette {}: {}", value, e))?
+                } else {
+                    Palette::from_hex(&value)
+                        .map_err(|e| format!("invalid palette {}: {}", value, e))?
+                });
+            }
+            "--quantize" => {
+                let name = args.next().ok_or(format!("missing value for {}", arg))?;
+                quantizer =
+                    Some(Quantizer::from_name(&name).ok_or(format!("unknown quantizer {}", name))?);
+            }
+            "--colors" => {
+                let value = args.next().ok_or(format!("missing value for {}", arg))?;
+                colors = Some(
+                    value
+                        .parse()
+                        .ok()
+                        .filter(|&n: &usize| n > 0)
+                        .ok_or(format!("invalid number of colors {}", value))?,
+                );
+            }
+            "--metric" => {
+                let name = args.next().ok_or(format!("missing value for {}", arg))?;
+                metric = Some(Metric::from_name(&name).ok_or(format!("unknown metric {}", name))?);
+            }
+            "--save-palette" => {
+                let path = args.next().ok_or(format!("missing value for {}", arg))?;
+                PaletteFormat::from_path(&path).map_err(|e| format!("{}: {}", path, e))?;
+                save_palette = Some(path);
+            }
+            _ if arg.starts_with('-') => return Err(format!("unknown option {}", arg)),
+            _ if file_path.is_none() => file_path = Some(arg),
+            _ => return Err(format!("unexpected argument {}", arg)),
+        }
+    }
 
-            increment_buffer(&mut buffer, i, j, 1, 0, error * 7.0 / 16.0);
-            increment_buffer(&mut buffer, i, j, -1, 1, error * 3.0 / 16.0);
-            increment_buffer(&mut buffer, i, j, 0, 1, error * 5.0 / 16.0);
-            increment_buffer(&mut buffer, i, j, 1, 1, error * 1.0 / 16.0);
+    if algorithms.is_empty() {
+        algorithms = DEFAULT_ALGORITHMS
+            .iter()
+            .map(|name| (name.to_string(), Algorithm::from_name(name).unwrap()))
+            .collect();
+    }
 
-            let pxl = if new_pxl == 1.0 { WHITE } else { BLACK };
-            new_img.put_pixel(x, y, pxl);
+    let uses_bayer = algorithms
+        .iter()
+        .any(|(_, algorithm)| matches!(algorithm, Algorithm::Bayer));
+    if let Some(size) = matrix_size {
+        if uses_bayer && !size.is_power_of_two() {
+            return Err(format!("bayer matrix size {} is not a power of two", size));
         }
     }
 
-    new_img
+    let uses_cmyk = algorithms
+        .iter()
+        .any(|(_, algorithm)| matches!(algorithm, Algorithm::Cmyk));
+    let quantize = match (quantizer, colors) {
+        (None, None) => None,
+        (quantizer, colors) => Some((
+            quantizer.unwrap_or_default().with_seed(seed),
+            colors.unwrap_or(16),
+        )),
+    };
+    if quantize.is_some() && palette.is_some() {
+        return Err("--palette and --quantize can't be used together".into());
+    }
+    let uses_palette = palette.is_some() || quantize.is_some();
+    if uses_cmyk && uses_palette {
+        return Err("cmyk separations can't use a palette".into());
+    }
+    if save_palette.is_some() && !uses_palette {
+        return Err("--save-palette needs a --palette or --quantize".into());
+    }
+    if metric.is_some() && !uses_palette {
+        return Err("--metric needs a --palette or --quantize".into());
+    }
+    if grayscale.is_some() && uses_palette {
+        return Err("--grayscale can't be used with a palette".into());
+    }
+    // L* turned back into linear light is the Rec. 709 luminance
+    if grayscale == Some(Grayscale::CieLightness) && linear {
+        return Err(
+            "--grayscale cie-lightness needs --no-linear, it is rec709 in linear light".into(),
+        );
+    }
+    if background.is_some() && alpha.is_some() {
+        return Err("--background and --alpha can't be used together".into());
+    }
+    if uses_cmyk && alpha.is_some() {
+        return Err("cmyk separations can't keep alpha".into());
+    }
+    if threshold.is_some() && uses_palette {
+        return Err("--threshold can't be used with a palette".into());
+    }
+    if levels.is_some() && uses_palette {
+        return Err("--levels can't be used with a palette".into());
+    }
+    if amplitude.is_some() && !matches!(modulation.as_deref(), Some("white" | "blue")) {
+        return Err("--modulation-amplitude needs white or blue --modulation".into());
+    }
+    let only_diffusion = algorithms
+        .iter()
+        .all(|(_, algorithm)| matches!(algorithm, Algorithm::Diffusion(_)));
+    if !only_diffusion {
+        let diffusion_options = [
+            ("--strength", strength.is_some()),
+            ("--clamp", clamp.is_some()),
+            ("--edges", edges.is_some()),
+            ("--modulation", modulation.is_some()),
+        ];
+        if let Some((option, _)) = diffusion_options.iter().find(|(_, given)| *given) {
+            return Err(format!("{} only applies to error diffusion", option));
+        }
+    }
+
+    let amplitude = amplitude.unwrap_or(0.5);
+    // Blue noise modulation uses a mask of the same size as the blue-noise
+    // algorithm
+    let mask_size = matrix_size.unwrap_or(64);
+    let modulation = match modulation.as_deref() {
+        None => None,
+        Some("white") => Some(Modulation::WhiteNoise { amplitude, seed }),
+        Some("blue") => Some(match &cache_dir {
+            Some(dir) => Modulation::BlueNoise {
+                mask: ThresholdMap::blue_noise_cached(mask_size, seed, dir)
+                    .map_err(|e| format!("failed to cache blue noise in {}: {}", dir, e))?,
+                amplitude,
+            },
+            None => Modulation::blue_noise(mask_size, amplitude, seed)
+                .ok_or("blue noise matrix size must be positive")?,
+        }),
+        Some(_) => Some(Modulation::ZhouFang { seed }),
+    };
+
+    Ok(Args {
+        file_path: file_path.ok_or("missing image path")?,
+        algorithms,
+        scan,
+        strength: strength.unwrap_or(1.0),
+        clamp,
+        edges: edges.unwrap_or_default(),
+        modulation,
+        matrix_size,
+        seed,
+        cache_dir,
+        lpi,
+        dpi,
+        angle,
+        dot,
+        cmyk_angles,
+        gcr,
+        preview,
+        background: background.unwrap_or(Rgb([255, 255, 255])),
+        alpha,
+        levels: levels.unwrap_or_default(),
+        threshold,
+        grayscale: grayscale.unwrap_or_default(),
+        linear,
+        palette,
+        quantize,
+        metric,
+        save_palette,
+    })
 }
 
 fn main() {
-    let args: Vec<String> = std::env::args().collect();
-    if args.len() < 2 {
-        println!("Usage: ./dithering /path/to/image");
-        return;
-    }
+    let mut args = match parse_args(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("Error: {}\n\n{}", e, usage());
+            process::exit(1);
+        }
+    };
 
-    let file_path = Path::new(&args[1]);
+    let file_path = Path::new(&args.file_path);
 
-    let img = ImageReader::open(file_path)
-        .expect(format!("failed to open {}", file_path.to_string_lossy()).as_str())
+    let mut img = ImageReader::open(file_path)
+        .unwrap_or_else(|_| panic!("failed to open {}", file_path.to_string_lossy()))
         .decode()
         .expect("failed to decode")
         .to_rgba8();
-
-    let atkinson_dither = atkinson(&img);
-    let floyd_dither = floyd_steinberg(&img);
+    if args.alpha.is_none() {
+        img = composite(&img, args.background);
+    }
 
     if let Err(e) = fs::create_dir_all("./out") {
         eprintln!("Error creating the output folder, {:?}", e);
         return;
     }
 
+    if let Some((quantizer, colors)) = args.quantize {
+        args.palette = quantizer.palette(&img, colors);
+        if args.palette.is_none() {
+            eprintln!("Error: can't take a palette from an empty image");
+            process::exit(1);
+        }
+    }
+
+    if let Some(metric) = args.metric {
+        args.palette = args.palette.map(|palette| palette.with_metric(metric));
+    }
+
+    if let (Some(palette), Some(path)) = (&args.palette, &args.save_palette) {
+        if let Err(e) = palette.save(path) {
+            eprintln!("Error saving the palette to {}: {}", path, e);
+            process::exit(1);
+        }
+    }
+
     let file_name = file_path.file_stem().unwrap().to_string_lossy();
     let file_ext = file_path.extension().unwrap().to_string_lossy();
 
-    atkinson_dither
-        .save(Path::new(&format!(
-            "./out/{}.atkinson.{}",
-            file_name, file_ext
-        )))
-        .expect("failed to save");
-
-    floyd_dither
-        .save(Path::new(&format!(
-            "./out/{}.floyd.{}",
-            file_name, file_ext
-        )))
-        .expect("failed to save");
+    for (name, algorithm) in &args.algorithms {
+        let outputs = match algorithm.render(&args, &img) {
+            Ok(outputs) => outputs,
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                process::exit(1);
+            }
+        };
+
+        for (suffix, output) in outputs {
+            let name = match suffix {
+                Some(suffix) => format!("{}.{}", name, suffix),
+                None => name.clone(),
+            };
+            // Keeps transparency even when the input format can't
+            let ext = match output.color().has_alpha() {
+                true => "png".into(),
+                false => file_ext.clone(),
+            };
+            output
+                .save(Path::new(&format!("./out/{}.{}.{}", file_name, name, ext)))
+                .expect("failed to save");
+        }
+    }
 }
@@ -0,0 +1,66 @@
+use crate::color::{ciede2000, srgb_to_lab, srgb_to_oklab};
+
+/// How the distance between two colors is measured when looking for the
+/// nearest color of a [`Palette`](crate::Palette)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Metric {
+    /// Euclidean distance between sRGB values, fast but far from perception
+    #[default]
+    Rgb,
+    /// [Redmean](https://www.compuphase.com/cmetric.htm), euclidean RGB with
+    /// channel weights depending on the amount of red
+    Redmean,
+    /// CIE 1976 ΔE, euclidean distance in CIELAB
+    Cie76,
+    /// CIEDE2000 ΔE, CIELAB with corrections for hue, chroma and lightness
+    Ciede2000,
+    /// Euclidean distance in Oklab
+    Oklab,
+}
+
+impl Metric {
+    /// Looks up a metric by name, `rgb`, `redmean`, `cie76`, `ciede2000` or `oklab`
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "rgb" => Some(Metric::Rgb),
+            "redmean" => Some(Metric::Redmean),
+            "cie76" => Some(Metric::Cie76),
+            "ciede2000" => Some(Metric::Ciede2000),
+            "oklab" => Some(Metric::Oklab),
+            _ => None,
+        }
+    }
+
+    /// Distance between two sRGB colors with channels between 0 and 1
+    pub fn distance(self, a: [f32; 3], b: [f32; 3]) -> f32 {
+        let d = self.compare(self.prepare(a), self.prepare(b));
+        match self {
+            Metric::Ciede2000 => d,
+            _ => d.sqrt(),
+        }
+    }
+
+    /// Moves an sRGB color into the space the metric measures distances in
+    pub(crate) fn prepare(self, rgb: [f32; 3]) -> [f32; 3] {
+        match self {
+            Metric::Rgb => rgb,
+            Metric::Redmean => rgb.map(|c| c * 255.0),
+            Metric::Cie76 | Metric::Ciede2000 => srgb_to_lab(rgb),
+            Metric::Oklab => srgb_to_oklab(rgb),
+        }
+    }
+
+    /// Value growing with the distance between two prepared colors, the
+    /// squared distance for euclidean metrics
+    pub(crate) fn compare(self, a: [f32; 3], b: [f32; 3]) -> f32 {
+        let [dr, dg, db] = [a[0] - b[0], a[1] - b[1], a[2] - b[2]];
+        match self {
+            Metric::Rgb | Metric::Cie76 | Metric::Oklab => dr * dr + dg * dg + db * db,
+            Metric::Redmean => {
+                let r = (a[0] + b[0]) / 2.0;
+                (2.0 + r / 256.0) * dr * dr + 4.0 * dg * dg + (2.0 + (255.0 - r) / 256.0) * db * db
+            }
+            Metric::Ciede2000 => ciede2000(a, b),
+        }
+    }
+}
@@ -0,0 +1,112 @@
+use crate::rng::Rng;
+use crate::target::stored_gray;
+use crate::ThresholdMap;
+
+/// Gray values, out of 255, where Zhou and Fang tabulate the modulation
+/// strength, with the strength at each. Mirrored for lighter grays
+const ZHOU_FANG_STRENGTH: [(f32, f32); 9] = [
+    (0.0, 0.0),
+    (44.0, 0.34),
+    (64.0, 0.5),
+    (85.0, 1.0),
+    (95.0, 0.17),
+    (102.0, 0.5),
+    (107.0, 0.7),
+    (112.0, 0.79),
+    (127.0, 1.0),
+];
+
+/// Moves the threshold [`ErrorDiffusion`](crate::ErrorDiffusion) compares
+/// each pixel to, which breaks up the regular textures it leaves in
+/// mid-tones
+///
+/// Thresholds are given as a fraction of the gap between the two levels
+/// around a pixel, where 0.5 is the usual cut, and amplitudes go from 0 to
+/// 1. The same seed always gives the same output
+#[derive(Debug, Clone, PartialEq)]
+pub enum Modulation {
+    /// Uniform random offsets, up to half of `amplitude` either way
+    WhiteNoise { amplitude: f32, seed: u64 },
+    /// Offsets read from a mask tiled over the image, up to half of
+    /// `amplitude` either way. Blue noise adds no low frequencies, so it
+    /// doesn't show as grain
+    BlueNoise { mask: ThresholdMap, amplitude: f32 },
+    /// Random offsets scaled by the intensity of each pixel, as in Zhou and
+    /// Fang's "Improving mid-tone quality of variable-coefficient error
+    /// diffusion using threshold modulation" (SIGGRAPH 2003). Strongest in
+    /// the mid-tones, where textures show the most, and off in the
+    /// highlights and shadows
+    ZhouFang { seed: u64 },
+}
+
+impl Modulation {
+    /// Blue noise offsets from a `size`x`size` mask generated from `seed`,
+    /// see [`ThresholdMap::blue_noise`]
+    ///
+    /// ## Returns
+    /// None if `size` is 0
+    pub fn blue_noise(size: u32, amplitude: f32, seed: u64) -> Option<Self> {
+        Some(Modulation::BlueNoise {
+            mask: ThresholdMap::blue_noise(size, seed)?,
+            amplitude,
+        })
+    }
+
+    /// Threshold of every pixel of a `w`x`h` image, in row-major order
+    ///
+    /// ## Parameters
+    /// - `values`: Pixel values before any error is added, in row-major order
+    /// - `linear`: Whether `values` are in linear light
+    pub(crate) fn thresholds<const N: usize>(
+        &self,
+        w: u32,
+        h: u32,
+        values: &[[f32; N]],
+        linear: bool,
+    ) -> Vec<f32> {
+        match self {
+            Modulation::WhiteNoise { amplitude, seed } => {
+                let mut rng = Rng::new(*seed);
+                (0..values.len())
+                    .map(|_| 0.5 + (rng.next_f64() as f32 - 0.5) * amplitude)
+                    .collect()
+            }
+            Modulation::BlueNoise { mask, amplitude } => (0..h)
+                .flat_map(|y| (0..w).map(move |x| (x, y)))
+                .map(|(x, y)| 0.5 + (mask.threshold(x, y) - 0.5) * amplitude)
+                .collect(),
+            Modulation::ZhouFang { seed } => {
+                let mut rng = Rng::new(*seed);
+                values
+                    .iter()
+                    .map(|&value| {
+                        // Offsets span up to half the gap, like the 128 out
+                        // of 256 of the paper
+                        let amplitude = zhou_fang_strength(stored_gray(value, linear)) / 2.0;
+                        0.5 + (rng.next_f64() as f32 - 0.5) * amplitude
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+/// Modulation strength of Zhou and Fang at `gray`, between 0 and 1,
+/// interpolated between the tabulated grays
+fn zhou_fang_strength(gray: f32) -> f32 {
+    let gray = gray.clamp(0.0, 1.0) * 255.0;
+    // Symmetric around mid-gray
+    let gray = if gray > 127.5 { 255.0 - gray } else { gray };
+    let upper = ZHOU_FANG_STRENGTH
+        .iter()
+        .position(|&(level, _)| gray <= level)
+        .unwrap_or(ZHOU_FANG_STRENGTH.len() - 1);
+    if upper == 0 {
+        return ZHOU_FANG_STRENGTH[0].1;
+    }
+    let ((low, from), (high, to)) = (ZHOU_FANG_STRENGTH[upper - 1], ZHOU_FANG_STRENGTH[upper]);
+    if gray >= high {
+        return to;
+    }
+    from + (to - from) * (gray - low) / (high - low)
+}
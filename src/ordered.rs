@@ -0,0 +1,241 @@
+use image::error::{ParameterError, ParameterErrorKind};
+use image::{GrayImage, ImageBuffer, ImageError, ImageResult, Luma, RgbImage, RgbaImage};
+use std::path::Path;
+
+use crate::target::{dither_gray, dither_palette, Target};
+use crate::{Ditherer, GrayLevels, Grayscale, Palette};
+
+/// A matrix of thresholds between 0 and 1, tiled across the image
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThresholdMap {
+    width: u32,
+    height: u32,
+    values: Vec<f32>,
+}
+
+impl ThresholdMap {
+    /// Creates a map from its thresholds in row-major order
+    ///
+    /// ## Parameters
+    /// - `width`: Width of the map, at least 1
+    /// - `height`: Height of the map, at least 1
+    /// - `values`: `width * height` thresholds between 0 and 1
+    /// ## Returns
+    /// None if the dimensions don't match the values
+    pub fn new(width: u32, height: u32, values: Vec<f32>) -> Option<Self> {
+        if width == 0 || height == 0 || values.len() != width as usize * height as usize {
+            return None;
+        }
+
+        Some(Self {
+            width,
+            height,
+            values,
+        })
+    }
+
+    /// Generates the `size`x`size` [Bayer matrix](https://en.wikipedia.org/wiki/Ordered_dithering).
+    ///
+    /// Index matrices are built recursively from the 2x2 one
+    /// ```plaintext
+    ///          | 4M + 0 | 4M + 2 |
+    /// M(2n) =  | 4M + 3 | 4M + 1 |
+    /// ````
+    /// and index `i` becomes the threshold `(i + 0.5) / size²`
+    ///
+    /// ## Parameters
+    /// - `size`: Side of the matrix, a power of two up to 32768
+    /// ## Returns
+    /// None if `size` is not a power of two or too large
+    pub fn bayer(size: u32) -> Option<Self> {
+        if !size.is_power_of_two() {
+            return None;
+        }
+        // Every index of the matrix has to fit in a u32
+        let count = size.checked_mul(size)?;
+
+        let mut index = vec![0u32];
+        let mut n = 1;
+        while n < size {
+            let mut next = vec![0u32; (4 * n * n) as usize];
+            for y in 0..n {
+                for x in 0..n {
+                    let m = 4 * index[(y * n + x) as usize];
+                    let at = |x: u32, y: u32| (y * 2 * n + x) as usize;
+                    next[at(x, y)] = m;
+                    next[at(x + n, y)] = m + 2;
+                    next[at(x, y + n)] = m + 3;
+                    next[at(x + n, y + n)] = m + 1;
+                }
+            }
+            index = next;
+            n *= 2;
+        }
+
+        let values = index
+            .into_iter()
+            .map(|i| (i as f32 + 0.5) / count as f32)
+            .collect();
+        Self::new(size, size, values)
+    }
+
+    /// Reads any image as a threshold map, such as a halftone screen or a map
+    /// saved by [`ThresholdMap::save`]. See [`ThresholdMap::from_image`] for
+    /// how its levels are turned into thresholds
+    ///
+    /// ## Returns
+    /// The error of the image crate, or a parameter error if the image is
+    /// empty
+    pub fn load(path: impl AsRef<Path>) -> ImageResult<Self> {
+        let img = image::open(path)?;
+        Self::from_image(&img.to_luma16()).ok_or_else(|| {
+            ImageError::Parameter(ParameterError::from_kind(ParameterErrorKind::Generic(
+                "threshold map is empty".into(),
+            )))
+        })
+    }
+
+    /// Turns the levels of a grayscale image into thresholds
+    ///
+    /// Levels are normalised by rank, so the thresholds are evenly spread
+    /// between 0 and 1 however many levels the image uses and whichever
+    /// range they cover. Pixels sharing a level share a threshold
+    ///
+    /// ## Parameters
+    /// - `img`: Grayscale image, darker pixels turn white first
+    /// ## Returns
+    /// None if the image is empty
+    pub fn from_image(img: &ImageBuffer<Luma<u16>, Vec<u16>>) -> Option<Self> {
+        let (width, height) = img.dimensions();
+        let count = width as usize * height as usize;
+
+        let mut histogram = vec![0usize; 1 << 16];
+        for Luma([v]) in img.pixels() {
+            histogram[*v as usize] += 1;
+        }
+
+        // Each level sits in the middle of the ranks its pixels take
+        let mut below = 0;
+        let mut levels = vec![0.0; 1 << 16];
+        for (level, &n) in histogram.iter().enumerate() {
+            levels[level] = (below as f32 + n as f32 / 2.0) / count as f32;
+            below += n;
+        }
+
+        let values = img.pixels().map(|Luma([v])| levels[*v as usize]).collect();
+        Self::new(width, height, values)
+    }
+
+    /// Saves the map as a 16-bit grayscale image
+    pub fn save(&self, path: impl AsRef<Path>) -> ImageResult<()> {
+        let img: ImageBuffer<Luma<u16>, Vec<u16>> =
+            ImageBuffer::from_fn(self.width, self.height, |x, y| {
+                Luma([(self.threshold(x, y) * 65535.0).round() as u16])
+            });
+        img.save(path)
+    }
+
+    pub fn dimensions(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    /// Threshold for the image pixel at (x, y), with the map repeated over the image
+    pub fn threshold(&self, x: u32, y: u32) -> f32 {
+        self.values[((y % self.height) * self.width + x % self.width) as usize]
+    }
+}
+
+/// Ordered dithering, every pixel is compared against its own threshold in a
+/// [`ThresholdMap`]
+///
+/// Unlike error diffusion the result of each pixel only depends on its own
+/// value, so flat areas always get the same pattern, which compresses well and
+/// stays stable across animation frames
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ordered {
+    map: ThresholdMap,
+    grayscale: Grayscale,
+    linear: bool,
+}
+
+impl Ordered {
+    pub fn new(map: ThresholdMap) -> Self {
+        Self {
+            map,
+            grayscale: Grayscale::default(),
+            linear: true,
+        }
+    }
+
+    /// Sets how pixels are turned into gray values for black and white
+    /// output, [`Grayscale::Rec709`] by default
+    pub fn with_grayscale(mut self, grayscale: Grayscale) -> Self {
+        self.grayscale = grayscale;
+        self
+    }
+
+    /// Sets whether values are compared to the thresholds in linear light,
+    /// on by default, so the share of white pixels matches the luminance
+    pub fn with_linear(mut self, linear: bool) -> Self {
+        self.linear = linear;
+        self
+    }
+
+    /// Ordered dithering with a `size`x`size` Bayer matrix, see [`ThresholdMap::bayer`]
+    pub fn bayer(size: u32) -> Option<Self> {
+        ThresholdMap::bayer(size).map(Self::new)
+    }
+
+    pub fn map(&self) -> &ThresholdMap {
+        &self.map
+    }
+
+    pub fn grayscale(&self) -> Grayscale {
+        self.grayscale
+    }
+
+    pub fn linear(&self) -> bool {
+        self.linear
+    }
+}
+
+impl Ordered {
+    /// Offsets every value by its threshold, spread over the gap between
+    /// neighbouring target values, and picks the nearest one
+    fn apply<const N: usize>(
+        &self,
+        w: u32,
+        values: &[[f32; N]],
+        target: &dyn Target<N>,
+    ) -> Vec<usize> {
+        values
+            .iter()
+            .enumerate()
+            .map(|(i, value)| {
+                let (x, y) = (i as u32 % w, i as u32 / w);
+                target.ordered(*value, self.map.threshold(x, y))
+            })
+            .collect()
+    }
+}
+
+impl Ditherer for Ordered {
+    fn dither_levels(&self, img: &RgbaImage, levels: &GrayLevels) -> GrayImage {
+        let w = img.width();
+        dither_gray(
+            img,
+            levels,
+            self.grayscale,
+            self.linear,
+            None,
+            |values, target| self.apply(w, values, target),
+        )
+    }
+
+    fn dither_palette(&self, img: &RgbaImage, palette: &Palette) -> RgbImage {
+        let w = img.width();
+        dither_palette(img, palette, self.linear, |values, target| {
+            self.apply(w, values, target)
+        })
+    }
+}
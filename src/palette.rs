@@ -0,0 +1,237 @@
+use image::{ImageBuffer, Rgb, RgbImage};
+use std::{borrow::Cow, cell::Cell, error::Error, fmt};
+
+use crate::color::{linear_to_srgb, srgb_to_linear};
+use crate::target::Target;
+use crate::Metric;
+
+/// Points on each side of the cache of [`Matcher`]
+const GRID: usize = 64;
+
+/// Reasons a palette can be rejected
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PaletteError {
+    /// A color is not written as `#rrggbb`
+    InvalidColor(String),
+    /// The palette has no colors
+    Empty,
+}
+
+impl fmt::Display for PaletteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PaletteError::InvalidColor(color) => write!(f, "invalid color {}", color),
+            PaletteError::Empty => write!(f, "palette has no colors"),
+        }
+    }
+}
+
+impl Error for PaletteError {}
+
+/// Fixed set of colors an image is dithered to
+///
+/// ```no_run
+/// use dithering::{DiffusionKernel, Ditherer, ErrorDiffusion, Palette};
+///
+/// let img = image::open("rei.jpeg").unwrap().to_rgba8();
+/// let palette = Palette::from_hex("#000000, #ff0000, #ffffff").unwrap();
+/// let dithered = ErrorDiffusion::new(DiffusionKernel::FLOYD_STEINBERG).dither_palette(&img, &palette);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Palette {
+    colors: Cow<'static, [Rgb<u8>]>,
+    metric: Metric,
+}
+
+impl Palette {
+    /// ## Returns
+    /// None if `colors` is empty
+    pub fn new(colors: Vec<Rgb<u8>>) -> Option<Self> {
+        if colors.is_empty() {
+            return None;
+        }
+        Some(Self {
+            colors: Cow::Owned(colors),
+            metric: Metric::Rgb,
+        })
+    }
+
+    /// Same as [`Palette::new`] for colors known at compile time, so
+    /// built-in palettes can be constants
+    ///
+    /// ## Panics
+    /// If `colors` is empty
+    pub const fn from_static(colors: &'static [Rgb<u8>]) -> Self {
+        assert!(!colors.is_empty(), "palette has no colors");
+        Self {
+            colors: Cow::Borrowed(colors),
+            metric: Metric::Rgb,
+        }
+    }
+
+    /// Parses a list of `#rrggbb` colors separated by commas or whitespace.
+    /// The `#` is optional
+    pub fn from_hex(list: &str) -> Result<Self, PaletteError> {
+        let colors = list
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|color| !color.is_empty())
+            .map(parse_hex)
+            .collect::<Result<_, _>>()?;
+        Self::new(colors).ok_or(PaletteError::Empty)
+    }
+
+    /// Sets how the nearest color is picked, [`Metric::Rgb`] by default
+    pub fn with_metric(mut self, metric: Metric) -> Self {
+        self.metric = metric;
+        self
+    }
+
+    pub fn colors(&self) -> &[Rgb<u8>] {
+        &self.colors
+    }
+
+    pub fn metric(&self) -> Metric {
+        self.metric
+    }
+
+    /// Index of the color closest to `color` under the palette's metric
+    pub fn nearest(&self, color: Rgb<u8>) -> usize {
+        self.matcher(false, false)
+            .search(color.0.map(|c| f32::from(c) / 255.0))
+    }
+
+    /// Prepares the nearest color lookups of a dithering run. The cache is
+    /// only worth it for slow metrics and images with many pixels
+    ///
+    /// ## Parameters
+    /// - `cached`: Cache the answers of perceptual metrics
+    /// - `linear`: Values are in linear light rather than sRGB
+    pub(crate) fn matcher(&self, cached: bool, linear: bool) -> Matcher<'_> {
+        let cache = match self.metric {
+            Metric::Rgb => None,
+            _ if !cached => None,
+            _ => Some(vec![Cell::new(u32::MAX); GRID * GRID * GRID]),
+        };
+        let srgb = |color: &Rgb<u8>| color.0.map(|c| f32::from(c) / 255.0);
+        Matcher {
+            palette: self,
+            linear,
+            values: self
+                .colors
+                .iter()
+                .map(|color| match linear {
+                    true => srgb(color).map(srgb_to_linear),
+                    false => srgb(color),
+                })
+                .collect(),
+            prepared: self
+                .colors
+                .iter()
+                .map(|color| self.metric.prepare(srgb(color)))
+                .collect(),
+            cache,
+        }
+    }
+
+    /// Builds an image from the palette index of each pixel, in row-major order
+    pub(crate) fn image(&self, w: u32, h: u32, indices: &[usize]) -> RgbImage {
+        ImageBuffer::from_fn(w, h, |x, y| {
+            self.colors[indices[y as usize * w as usize + x as usize]]
+        })
+    }
+}
+
+/// Parses a single `#rrggbb` or `rrggbb` color
+pub(crate) fn parse_hex(color: &str) -> Result<Rgb<u8>, PaletteError> {
+    let hex = color.strip_prefix('#').unwrap_or(color);
+    let invalid = || PaletteError::InvalidColor(color.to_string());
+    if hex.len() != 6 || !hex.is_ascii() {
+        return Err(invalid());
+    }
+
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| invalid());
+    Ok(Rgb([channel(0)?, channel(2)?, channel(4)?]))
+}
+
+/// Finds the nearest color of a palette under its metric
+///
+/// [`Metric::Rgb`] compares values in the space the ditherer works in, sRGB
+/// or linear light, so ordered dithering thresholds stay halfway between
+/// colors. Perceptual metrics always compare sRGB colors, and are too slow to
+/// compare every pixel against every color, so their answers are cached on a
+/// 64x64x64 grid of colors, filled in as the image needs them. Values between
+/// grid points snap to the closest one, which error diffusion then corrects
+/// like any other error
+pub(crate) struct Matcher<'a> {
+    palette: &'a Palette,
+    linear: bool,
+    /// Palette colors in the space of the ditherer
+    values: Vec<[f32; 3]>,
+    /// Palette colors converted to the space of the metric
+    prepared: Vec<[f32; 3]>,
+    /// Nearest color of each grid point, `u32::MAX` until it is needed
+    cache: Option<Vec<Cell<u32>>>,
+}
+
+impl Matcher<'_> {
+    /// Nearest color to an sRGB color, by comparing against every color of
+    /// the palette
+    fn search(&self, value: [f32; 3]) -> usize {
+        let metric = self.palette.metric;
+        closest(&self.prepared, metric.prepare(value), |a, b| {
+            metric.compare(a, b)
+        })
+    }
+}
+
+/// Index of the color closest to `value`, the first one on ties
+fn closest(
+    colors: &[[f32; 3]],
+    value: [f32; 3],
+    compare: impl Fn([f32; 3], [f32; 3]) -> f32,
+) -> usize {
+    let mut best = (0, f32::INFINITY);
+    for (i, color) in colors.iter().enumerate() {
+        let distance = compare(value, *color);
+        if distance < best.1 {
+            best = (i, distance);
+        }
+    }
+    best.0
+}
+
+impl Target<3> for Matcher<'_> {
+    fn nearest(&self, value: [f32; 3]) -> usize {
+        if self.palette.metric == Metric::Rgb {
+            return closest(&self.values, value, |a, b| Metric::Rgb.compare(a, b));
+        }
+
+        let value = match self.linear {
+            true => value.map(linear_to_srgb),
+            false => value,
+        };
+        let Some(cache) = &self.cache else {
+            return self.search(value);
+        };
+
+        let [r, g, b] = value.map(|v| (v.clamp(0.0, 1.0) * (GRID - 1) as f32).round() as usize);
+        let cell = &cache[(r * GRID + g) * GRID + b];
+        if cell.get() == u32::MAX {
+            let point = [r, g, b].map(|v| v as f32 / (GRID - 1) as f32);
+            cell.set(self.search(point) as u32);
+        }
+        cell.get() as usize
+    }
+
+    fn value(&self, index: usize) -> [f32; 3] {
+        self.values[index]
+    }
+
+    /// Shifts `value` by the threshold over the spacing of a palette with as
+    /// many colors evenly spread over the RGB cube
+    fn ordered(&self, value: [f32; 3], threshold: f32) -> usize {
+        let spread = 1.0 / ((self.palette.colors.len() as f32).cbrt() - 1.0).max(1.0);
+        let offset = (0.5 - threshold) * spread;
+        self.nearest(value.map(|v| v + offset))
+    }
+}
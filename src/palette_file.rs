@@ -0,0 +1,482 @@
+//! Loading and saving [`Palette`]s in the formats of common image editors
+//!
+//! The format is picked from the file extension:
+//!
+//! | Extension | Format |
+//! |-----------|--------|
+//! | `.gpl`    | GIMP palette |
+//! | `.act`    | Adobe Color Table, 256 RGB triplets and an optional color count |
+//! | `.aco`    | Adobe Color Swatch, RGB, HSB, CMYK and grayscale swatches |
+//! | `.pal`    | JASC-PAL, as written by Paint Shop Pro and Lospec |
+//! | `.txt`    | Paint.NET palette, one `AARRGGBB` color per line |
+//! | `.hex`    | One `rrggbb` color per line, as exported by Lospec |
+//!
+//! Alpha, color names and swatch groups are ignored when loading, and are
+//! not written back when saving.
+
+use image::Rgb;
+use std::{error::Error, fmt, fs, io, path::Path};
+
+use crate::palette::parse_hex;
+use crate::Palette;
+
+/// Reasons a palette file can be rejected
+#[derive(Debug)]
+pub enum PaletteFileError {
+    /// The file could not be read or written
+    Io(io::Error),
+    /// The extension doesn't match any known format
+    UnknownFormat(String),
+    /// A line of a text format could not be parsed
+    Syntax { line: usize, message: String },
+    /// A binary format is truncated or has unexpected values
+    Invalid(String),
+    /// The file has no colors
+    Empty,
+    /// The palette has more colors than the format can hold
+    TooManyColors { count: usize, max: usize },
+}
+
+impl fmt::Display for PaletteFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PaletteFileError::Io(e) => write!(f, "failed to access palette: {}", e),
+            PaletteFileError::UnknownFormat(ext) => {
+                write!(f, "unknown palette format \"{}\"", ext)
+            }
+            PaletteFileError::Syntax { line, message } => write!(f, "line {}: {}", line, message),
+            PaletteFileError::Invalid(message) => write!(f, "invalid palette: {}", message),
+            PaletteFileError::Empty => write!(f, "palette has no colors"),
+            PaletteFileError::TooManyColors { count, max } => write!(
+                f,
+                "palette has {} colors, the format holds at most {}",
+                count, max
+            ),
+        }
+    }
+}
+
+impl Error for PaletteFileError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            PaletteFileError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for PaletteFileError {
+    fn from(e: io::Error) -> Self {
+        PaletteFileError::Io(e)
+    }
+}
+
+/// File formats palettes can be read from and written to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PaletteFormat {
+    /// GIMP `.gpl`
+    Gpl,
+    /// Adobe Color Table `.act`
+    Act,
+    /// Adobe Color Swatch `.aco`
+    Aco,
+    /// JASC-PAL `.pal`
+    Jasc,
+    /// Paint.NET `.txt`
+    PaintNet,
+    /// Plain `.hex` list
+    Hex,
+}
+
+impl PaletteFormat {
+    /// Every format, in the order of the table in the [module docs](self)
+    pub const ALL: [Self; 6] = [
+        PaletteFormat::Gpl,
+        PaletteFormat::Act,
+        PaletteFormat::Aco,
+        PaletteFormat::Jasc,
+        PaletteFormat::PaintNet,
+        PaletteFormat::Hex,
+    ];
+
+    /// Extension of the format, without the dot
+    pub fn extension(self) -> &'static str {
+        match self {
+            PaletteFormat::Gpl => "gpl",
+            PaletteFormat::Act => "act",
+            PaletteFormat::Aco => "aco",
+            PaletteFormat::Jasc => "pal",
+            PaletteFormat::PaintNet => "txt",
+            PaletteFormat::Hex => "hex",
+        }
+    }
+
+    /// Picks the format from the extension of `path`, ignoring case
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self, PaletteFileError> {
+        let ext = path
+            .as_ref()
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        Self::ALL
+            .into_iter()
+            .find(|format| format.extension() == ext)
+            .ok_or(PaletteFileError::UnknownFormat(ext))
+    }
+
+    /// Reads a palette from the content of a file in this format
+    pub fn parse(self, data: &[u8]) -> Result<Palette, PaletteFileError> {
+        let colors = match self {
+            PaletteFormat::Act => parse_act(data)?,
+            PaletteFormat::Aco => parse_aco(data)?,
+            PaletteFormat::Jasc if data.starts_with(b"RIFF") => {
+                return Err(PaletteFileError::Invalid(
+                    "RIFF palettes are not supported, only JASC-PAL".into(),
+                ))
+            }
+            _ => {
+                let text = std::str::from_utf8(data)
+                    .map_err(|_| PaletteFileError::Invalid("file is not valid UTF-8".into()))?;
+                match self {
+                    PaletteFormat::Gpl => parse_gpl(text)?,
+                    PaletteFormat::Jasc => parse_jasc(text)?,
+                    PaletteFormat::PaintNet => parse_paint_net(text)?,
+                    _ => parse_hex_lines(text)?,
+                }
+            }
+        };
+        Palette::new(colors).ok_or(PaletteFileError::Empty)
+    }
+
+    /// Writes `palette` as the content of a file in this format
+    pub fn write(self, palette: &Palette) -> Result<Vec<u8>, PaletteFileError> {
+        let colors = palette.colors();
+        let hex = |Rgb([r, g, b]): &Rgb<u8>| format!("{:02x}{:02x}{:02x}", r, g, b);
+
+        let data = match self {
+            PaletteFormat::Gpl => {
+                let mut text = String::from("GIMP Palette\n#\n");
+                for color @ Rgb([r, g, b]) in colors {
+                    text += &format!("{:3} {:3} {:3}\t#{}\n", r, g, b, hex(color));
+                }
+                text.into_bytes()
+            }
+            PaletteFormat::Act => {
+                if colors.len() > 256 {
+                    return Err(PaletteFileError::TooManyColors {
+                        count: colors.len(),
+                        max: 256,
+                    });
+                }
+                let mut data = vec![0; 768];
+                for (i, color) in colors.iter().enumerate() {
+                    data[i * 3..i * 3 + 3].copy_from_slice(&color.0);
+                }
+                data.extend((colors.len() as u16).to_be_bytes());
+                // No transparent color
+                data.extend(0xffffu16.to_be_bytes());
+                data
+            }
+            PaletteFormat::Aco => {
+                if colors.len() > u16::MAX as usize {
+                    return Err(PaletteFileError::TooManyColors {
+                        count: colors.len(),
+                        max: u16::MAX as usize,
+                    });
+                }
+                let mut data = Vec::new();
+                // Version 1 swatches, followed by the same swatches with
+                // names for version 2 readers
+                for version in [1u16, 2] {
+                    data.extend(version.to_be_bytes());
+                    data.extend((colors.len() as u16).to_be_bytes());
+                    for color in colors {
+                        data.extend(0u16.to_be_bytes());
+                        for c in color.0 {
+                            data.extend((u16::from(c) * 257).to_be_bytes());
+                        }
+                        data.extend(0u16.to_be_bytes());
+                        if version == 2 {
+                            let name: Vec<u16> =
+                                format!("#{}", hex(color)).encode_utf16().collect();
+                            data.extend((name.len() as u32 + 1).to_be_bytes());
+                            for unit in name.into_iter().chain([0]) {
+                                data.extend(unit.to_be_bytes());
+                            }
+                        }
+                    }
+                }
+                data
+            }
+            PaletteFormat::Jasc => {
+                let mut text = format!("JASC-PAL\r\n0100\r\n{}\r\n", colors.len());
+                for Rgb([r, g, b]) in colors {
+                    text += &format!("{} {} {}\r\n", r, g, b);
+                }
+                text.into_bytes()
+            }
+            PaletteFormat::PaintNet => {
+                let mut text = String::from("; paint.net Palette File\n");
+                for color in colors {
+                    text += &format!("FF{}\n", hex(color).to_uppercase());
+                }
+                text.into_bytes()
+            }
+            PaletteFormat::Hex => colors
+                .iter()
+                .map(|color| hex(color) + "\n")
+                .collect::<String>()
+                .into_bytes(),
+        };
+        Ok(data)
+    }
+}
+
+impl Palette {
+    /// Reads a palette file, in the format matching its extension. See the
+    /// [module docs](crate::palette_file) for the supported formats
+    ///
+    /// ## Parameters
+    /// - `path`: Path of the palette file
+    /// ## Returns
+    /// The palette, or the first problem found in the file
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, PaletteFileError> {
+        let format = PaletteFormat::from_path(&path)?;
+        format.parse(&fs::read(path)?)
+    }
+
+    /// Writes the palette in the format matching the extension of `path`
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), PaletteFileError> {
+        let format = PaletteFormat::from_path(&path)?;
+        fs::write(path, format.write(self)?)?;
+        Ok(())
+    }
+}
+
+/// Non-empty lines of `text` along with their 1-based number, trimmed
+fn lines(text: &str) -> impl Iterator<Item = (usize, &str)> {
+    text.lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty())
+}
+
+fn syntax(line: usize, message: impl Into<String>) -> PaletteFileError {
+    PaletteFileError::Syntax {
+        line,
+        message: message.into(),
+    }
+}
+
+/// Parses whitespace separated red, green and blue values between 0 and 255,
+/// ignoring anything after them
+fn parse_rgb<'a>(
+    line: usize,
+    mut values: impl Iterator<Item = &'a str>,
+) -> Result<Rgb<u8>, PaletteFileError> {
+    let mut channel = || {
+        let value = values
+            .next()
+            .ok_or_else(|| syntax(line, "expected red, green and blue values"))?;
+        value
+            .parse()
+            .map_err(|_| syntax(line, format!("invalid channel value {}", value)))
+    };
+    Ok(Rgb([channel()?, channel()?, channel()?]))
+}
+
+fn parse_gpl(text: &str) -> Result<Vec<Rgb<u8>>, PaletteFileError> {
+    let mut lines = lines(text);
+    match lines.next() {
+        Some((_, "GIMP Palette")) => {}
+        Some((line, _)) => return Err(syntax(line, "expected \"GIMP Palette\" header")),
+        None => return Err(PaletteFileError::Empty),
+    }
+
+    let mut colors = Vec::new();
+    for (line, content) in lines {
+        if content.starts_with('#')
+            || content.starts_with("Name:")
+            || content.starts_with("Columns:")
+        {
+            continue;
+        }
+        colors.push(parse_rgb(line, content.split_whitespace())?);
+    }
+    Ok(colors)
+}
+
+fn parse_jasc(text: &str) -> Result<Vec<Rgb<u8>>, PaletteFileError> {
+    let mut lines = lines(text);
+    let mut header = |expected: &str| match lines.next() {
+        Some((line, content)) => Ok((line, content)),
+        None => Err(syntax(
+            text.lines().count(),
+            format!("missing {}", expected),
+        )),
+    };
+
+    let (line, magic) = header("\"JASC-PAL\" header")?;
+    if magic != "JASC-PAL" {
+        return Err(syntax(line, "expected \"JASC-PAL\" header"));
+    }
+    let (line, version) = header("version")?;
+    if version != "0100" {
+        return Err(syntax(line, format!("unsupported version {}", version)));
+    }
+    let (line, count) = header("color count")?;
+    let count: usize = count
+        .parse()
+        .map_err(|_| syntax(line, format!("invalid color count {}", count)))?;
+
+    let colors = lines
+        .map(|(line, content)| parse_rgb(line, content.split_whitespace()))
+        .collect::<Result<Vec<_>, _>>()?;
+    if colors.len() != count {
+        return Err(PaletteFileError::Invalid(format!(
+            "header announces {} colors, found {}",
+            count,
+            colors.len()
+        )));
+    }
+    Ok(colors)
+}
+
+fn parse_paint_net(text: &str) -> Result<Vec<Rgb<u8>>, PaletteFileError> {
+    lines(text)
+        .filter(|(_, content)| !content.starts_with(';'))
+        .map(|(line, content)| {
+            if content.len() != 8 || !content.is_ascii() {
+                return Err(syntax(
+                    line,
+                    format!("expected an AARRGGBB color, got {}", content),
+                ));
+            }
+            parse_hex(&content[2..]).map_err(|e| syntax(line, e.to_string()))
+        })
+        .collect()
+}
+
+fn parse_hex_lines(text: &str) -> Result<Vec<Rgb<u8>>, PaletteFileError> {
+    lines(text)
+        .map(|(line, content)| parse_hex(content).map_err(|e| syntax(line, e.to_string())))
+        .collect()
+}
+
+fn parse_act(data: &[u8]) -> Result<Vec<Rgb<u8>>, PaletteFileError> {
+    let count = match data.len() {
+        768 => 256,
+        772 => match u16::from_be_bytes([data[768], data[769]]) {
+            count @ 1..=256 => count as usize,
+            count => {
+                return Err(PaletteFileError::Invalid(format!(
+                    "color count {} is not between 1 and 256",
+                    count
+                )))
+            }
+        },
+        len => {
+            return Err(PaletteFileError::Invalid(format!(
+                "color tables are 768 or 772 bytes long, got {}",
+                len
+            )))
+        }
+    };
+
+    Ok(data[..count * 3]
+        .chunks(3)
+        .map(|rgb| Rgb([rgb[0], rgb[1], rgb[2]]))
+        .collect())
+}
+
+/// Big-endian reader over the content of a swatch file
+struct Reader<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl Reader<'_> {
+    fn bytes(&mut self, len: usize) -> Result<&[u8], PaletteFileError> {
+        let bytes = self
+            .data
+            .get(self.offset..self.offset + len)
+            .ok_or_else(|| {
+                PaletteFileError::Invalid(format!("file ends early, at byte {}", self.data.len()))
+            })?;
+        self.offset += len;
+        Ok(bytes)
+    }
+
+    fn u16(&mut self) -> Result<u16, PaletteFileError> {
+        let bytes = self.bytes(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u32(&mut self) -> Result<u32, PaletteFileError> {
+        let bytes = self.bytes(4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+}
+
+fn parse_aco(data: &[u8]) -> Result<Vec<Rgb<u8>>, PaletteFileError> {
+    let mut reader = Reader { data, offset: 0 };
+
+    // Version 2 files start with a version 1 section, either one has every color
+    let version = reader.u16()?;
+    if version != 1 && version != 2 {
+        return Err(PaletteFileError::Invalid(format!(
+            "unsupported swatch version {}",
+            version
+        )));
+    }
+
+    let count = reader.u16()?;
+    let mut colors = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        let space = reader.u16()?;
+        let [w, x, y, z] = [reader.u16()?, reader.u16()?, reader.u16()?, reader.u16()?];
+        if version == 2 {
+            let len = reader.u32()? as usize;
+            reader.bytes(len * 2)?;
+        }
+        colors.push(aco_color(space, [w, x, y, z])?);
+    }
+    Ok(colors)
+}
+
+/// Converts a swatch to RGB from its color space and four raw values
+fn aco_color(space: u16, [w, x, y, z]: [u16; 4]) -> Result<Rgb<u8>, PaletteFileError> {
+    let unit = |v: u16| f32::from(v) / 65535.0;
+    let rgb = match space {
+        // RGB
+        0 => [unit(w), unit(x), unit(y)],
+        // HSB
+        1 => {
+            let (h, s, b) = (unit(w) * 6.0, unit(x), unit(y));
+            let f = h.fract();
+            let (p, q, t) = (b * (1.0 - s), b * (1.0 - s * f), b * (1.0 - s * (1.0 - f)));
+            match h as u32 % 6 {
+                0 => [b, t, p],
+                1 => [q, b, p],
+                2 => [p, b, t],
+                3 => [p, q, b],
+                4 => [t, p, b],
+                _ => [b, p, q],
+            }
+        }
+        // CMYK, stored as 65535 minus the ink
+        2 => {
+            let k = unit(z);
+            [unit(w) * k, unit(x) * k, unit(y) * k]
+        }
+        // Grayscale, the amount of black ink from 0 to 10000
+        8 => [1.0 - f32::from(w.min(10000)) / 10000.0; 3],
+        _ => {
+            return Err(PaletteFileError::Invalid(format!(
+                "unsupported color space {}",
+                space
+            )))
+        }
+    };
+    Ok(Rgb(rgb.map(|c| (c * 255.0).round() as u8)))
+}
@@ -0,0 +1,246 @@
+use image::Rgb;
+
+use crate::Palette;
+
+/// Color written as `0xrrggbb`
+const fn hex(rgb: u32) -> Rgb<u8> {
+    Rgb([(rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8])
+}
+
+/// Every combination of the 6 levels 0x00, 0x33, ..., 0xff on each channel
+const WEB_SAFE: [Rgb<u8>; 216] = {
+    let mut colors = [Rgb([0, 0, 0]); 216];
+    let mut i = 0;
+    while i < 216 {
+        colors[i] = Rgb([
+            (i / 36) as u8 * 0x33,
+            (i / 6 % 6) as u8 * 0x33,
+            (i % 6) as u8 * 0x33,
+        ]);
+        i += 1;
+    }
+    colors
+};
+
+impl Palette {
+    /// CGA 4-color mode, palette 0 at low intensity: black, green, red, brown
+    pub const CGA_0: Self =
+        Self::from_static(&[hex(0x000000), hex(0x00aa00), hex(0xaa0000), hex(0xaa5500)]);
+
+    /// CGA 4-color mode, palette 0 at high intensity: black, light green,
+    /// light red, yellow
+    pub const CGA_0_HIGH: Self =
+        Self::from_static(&[hex(0x000000), hex(0x55ff55), hex(0xff5555), hex(0xffff55)]);
+
+    /// CGA 4-color mode, palette 1 at low intensity: black, cyan, magenta,
+    /// light gray
+    pub const CGA_1: Self =
+        Self::from_static(&[hex(0x000000), hex(0x00aaaa), hex(0xaa00aa), hex(0xaaaaaa)]);
+
+    /// CGA 4-color mode, palette 1 at high intensity: black, light cyan,
+    /// light magenta, white
+    pub const CGA_1_HIGH: Self =
+        Self::from_static(&[hex(0x000000), hex(0x55ffff), hex(0xff55ff), hex(0xffffff)]);
+
+    /// CGA mode 5 at low intensity: black, cyan, red, light gray
+    pub const CGA_5: Self =
+        Self::from_static(&[hex(0x000000), hex(0x00aaaa), hex(0xaa0000), hex(0xaaaaaa)]);
+
+    /// CGA mode 5 at high intensity: black, light cyan, light red, white
+    pub const CGA_5_HIGH: Self =
+        Self::from_static(&[hex(0x000000), hex(0x55ffff), hex(0xff5555), hex(0xffffff)]);
+
+    /// The 16 colors of the default EGA palette
+    pub const EGA: Self = Self::from_static(&[
+        hex(0x000000),
+        hex(0x0000aa),
+        hex(0x00aa00),
+        hex(0x00aaaa),
+        hex(0xaa0000),
+        hex(0xaa00aa),
+        hex(0xaa5500),
+        hex(0xaaaaaa),
+        hex(0x555555),
+        hex(0x5555ff),
+        hex(0x55ff55),
+        hex(0x55ffff),
+        hex(0xff5555),
+        hex(0xff55ff),
+        hex(0xffff55),
+        hex(0xffffff),
+    ]);
+
+    /// The 4 greens of the original Game Boy (DMG) screen
+    pub const GAME_BOY: Self =
+        Self::from_static(&[hex(0x0f380f), hex(0x306230), hex(0x8bac0f), hex(0x9bbc0f)]);
+
+    /// The 16 colors of the PICO-8 fantasy console
+    pub const PICO_8: Self = Self::from_static(&[
+        hex(0x000000),
+        hex(0x1d2b53),
+        hex(0x7e2553),
+        hex(0x008751),
+        hex(0xab5236),
+        hex(0x5f574f),
+        hex(0xc2c3c7),
+        hex(0xfff1e8),
+        hex(0xff004d),
+        hex(0xffa300),
+        hex(0xffec27),
+        hex(0x00e436),
+        hex(0x29adff),
+        hex(0x83769c),
+        hex(0xff77a8),
+        hex(0xffccaa),
+    ]);
+
+    /// The 16 colors of the Commodore 64, as measured for the Pepto palette
+    pub const C64: Self = Self::from_static(&[
+        hex(0x000000),
+        hex(0xffffff),
+        hex(0x68372b),
+        hex(0x70a4b2),
+        hex(0x6f3d86),
+        hex(0x588d43),
+        hex(0x352879),
+        hex(0xb8c76f),
+        hex(0x6f4f25),
+        hex(0x433900),
+        hex(0x9a6759),
+        hex(0x444444),
+        hex(0x6c6c6c),
+        hex(0x9ad284),
+        hex(0x6c5eb5),
+        hex(0x959595),
+    ]);
+
+    /// The ZX Spectrum colors, normal then bright, with black only once
+    pub const ZX_SPECTRUM: Self = Self::from_static(&[
+        hex(0x000000),
+        hex(0x0000d7),
+        hex(0xd70000),
+        hex(0xd700d7),
+        hex(0x00d700),
+        hex(0x00d7d7),
+        hex(0xd7d700),
+        hex(0xd7d7d7),
+        hex(0x0000ff),
+        hex(0xff0000),
+        hex(0xff00ff),
+        hex(0x00ff00),
+        hex(0x00ffff),
+        hex(0xffff00),
+        hex(0xffffff),
+    ]);
+
+    /// The distinct colors of the NES PPU palette
+    pub const NES: Self = Self::from_static(&[
+        hex(0x7c7c7c),
+        hex(0x0000fc),
+        hex(0x0000bc),
+        hex(0x4428bc),
+        hex(0x940084),
+        hex(0xa80020),
+        hex(0xa81000),
+        hex(0x881400),
+        hex(0x503000),
+        hex(0x007800),
+        hex(0x006800),
+        hex(0x005800),
+        hex(0x004058),
+        hex(0x000000),
+        hex(0xbcbcbc),
+        hex(0x0078f8),
+        hex(0x0058f8),
+        hex(0x6844fc),
+        hex(0xd800cc),
+        hex(0xe40058),
+        hex(0xf83800),
+        hex(0xe45c10),
+        hex(0xac7c00),
+        hex(0x00b800),
+        hex(0x00a800),
+        hex(0x00a844),
+        hex(0x008888),
+        hex(0xf8f8f8),
+        hex(0x3cbcfc),
+        hex(0x6888fc),
+        hex(0x9878f8),
+        hex(0xf878f8),
+        hex(0xf85898),
+        hex(0xf87858),
+        hex(0xfca044),
+        hex(0xf8b800),
+        hex(0xb8f818),
+        hex(0x58d854),
+        hex(0x58f898),
+        hex(0x00e8d8),
+        hex(0x787878),
+        hex(0xfcfcfc),
+        hex(0xa4e4fc),
+        hex(0xb8b8f8),
+        hex(0xd8b8f8),
+        hex(0xf8b8f8),
+        hex(0xf8a4c0),
+        hex(0xf0d0b0),
+        hex(0xfce0a8),
+        hex(0xf8d878),
+        hex(0xd8f878),
+        hex(0xb8f8b8),
+        hex(0xb8f8d8),
+        hex(0x00fcfc),
+        hex(0xf8d8f8),
+    ]);
+
+    /// Black and white, as on the original Macintosh
+    pub const MAC: Self = Self::from_static(&[hex(0x000000), hex(0xffffff)]);
+
+    /// The 216 web-safe colors
+    pub const WEB_SAFE: Self = Self::from_static(&WEB_SAFE);
+
+    /// Black, white and red e-paper panels
+    pub const EPAPER_BWR: Self = Self::from_static(&[hex(0x000000), hex(0xffffff), hex(0xff0000)]);
+
+    /// Black, white and yellow e-paper panels
+    pub const EPAPER_BWY: Self = Self::from_static(&[hex(0x000000), hex(0xffffff), hex(0xffff00)]);
+
+    /// 7-color ACeP e-paper panels, with the nominal colors their drivers expect
+    pub const ACEP: Self = Self::from_static(&[
+        hex(0x000000),
+        hex(0xffffff),
+        hex(0x00ff00),
+        hex(0x0000ff),
+        hex(0xff0000),
+        hex(0xffff00),
+        hex(0xff8000),
+    ]);
+
+    /// Built-in palettes along with the name they are selected by
+    pub const NAMED: &'static [(&'static str, Self)] = &[
+        ("cga0", Self::CGA_0),
+        ("cga0-high", Self::CGA_0_HIGH),
+        ("cga1", Self::CGA_1),
+        ("cga1-high", Self::CGA_1_HIGH),
+        ("cga5", Self::CGA_5),
+        ("cga5-high", Self::CGA_5_HIGH),
+        ("ega", Self::EGA),
+        ("gameboy", Self::GAME_BOY),
+        ("pico8", Self::PICO_8),
+        ("c64", Self::C64),
+        ("zx-spectrum", Self::ZX_SPECTRUM),
+        ("nes", Self::NES),
+        ("mac", Self::MAC),
+        ("web-safe", Self::WEB_SAFE),
+        ("epaper-bwr", Self::EPAPER_BWR),
+        ("epaper-bwy", Self::EPAPER_BWY),
+        ("acep", Self::ACEP),
+    ];
+
+    /// Looks up a built-in palette by the name listed in [`Palette::NAMED`]
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::NAMED
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, palette)| palette.clone())
+    }
+}
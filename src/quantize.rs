@@ -0,0 +1,469 @@
+use image::{Rgb, RgbaImage};
+use std::collections::HashMap;
+
+use crate::{rng::Rng, Palette};
+
+/// Distinct color of an image along with the number of pixels using it
+type Bin = ([u8; 3], u64);
+
+/// Algorithms deriving a palette from the colors of an image
+///
+/// ```no_run
+/// use dithering::{DiffusionKernel, Ditherer, ErrorDiffusion, Quantizer};
+///
+/// let img = image::open("rei.jpeg").unwrap().to_rgba8();
+/// let palette = Quantizer::Wu.palette(&img, 16).unwrap();
+/// let dithered = ErrorDiffusion::new(DiffusionKernel::FLOYD_STEINBERG).dither_palette(&img, &palette);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Quantizer {
+    /// Heckbert's median cut, repeatedly splits the box of colors with the
+    /// widest channel at its median
+    #[default]
+    MedianCut,
+    /// Gervautz-Purgathofer octree, merges the least used branches of a tree
+    /// indexed by the bits of each channel
+    Octree,
+    /// Lloyd's k-means on the distinct colors, weighted by their use, starting
+    /// from a k-means++ pick driven by `seed`
+    KMeans { seed: u64 },
+    /// Wu's quantizer, splits boxes of a 32x32x32 color histogram where it
+    /// lowers the variance the most
+    Wu,
+}
+
+impl Quantizer {
+    /// Looks up a quantizer by name, `median-cut`, `octree`, `kmeans` or `wu`.
+    /// K-means starts with seed 0, see [`Quantizer::with_seed`]
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "median-cut" => Some(Quantizer::MedianCut),
+            "octree" => Some(Quantizer::Octree),
+            "kmeans" => Some(Quantizer::KMeans { seed: 0 }),
+            "wu" => Some(Quantizer::Wu),
+            _ => None,
+        }
+    }
+
+    /// Sets the seed of [`Quantizer::KMeans`], other quantizers are
+    /// deterministic and don't use one
+    pub fn with_seed(self, seed: u64) -> Self {
+        match self {
+            Quantizer::KMeans { .. } => Quantizer::KMeans { seed },
+            quantizer => quantizer,
+        }
+    }
+
+    /// Picks at most `colors` colors representing `img`
+    ///
+    /// ## Parameters
+    /// - `img`: Image to take the colors from, alpha is ignored
+    /// - `colors`: Maximum size of the palette
+    /// ## Returns
+    /// None if the image is empty or `colors` is 0
+    pub fn palette(&self, img: &RgbaImage, colors: usize) -> Option<Palette> {
+        let mut histogram: HashMap<[u8; 3], u64> = HashMap::new();
+        for pxl in img.pixels() {
+            let [r, g, b, _] = pxl.0;
+            *histogram.entry([r, g, b]).or_default() += 1;
+        }
+        let mut bins: Vec<Bin> = histogram.into_iter().collect();
+        bins.sort_unstable();
+
+        if bins.is_empty() || colors == 0 {
+            return None;
+        }
+
+        let picked = if bins.len() <= colors {
+            bins.iter().map(|(color, _)| *color).collect()
+        } else {
+            match self {
+                Quantizer::MedianCut => median_cut(bins, colors),
+                Quantizer::Octree => octree(&bins, colors),
+                Quantizer::KMeans { seed } => k_means(&bins, colors, *seed),
+                Quantizer::Wu => wu(&bins, colors),
+            }
+        };
+        Palette::new(picked.into_iter().map(Rgb).collect())
+    }
+}
+
+/// Average color of `bins`, weighted by their use
+fn mean(bins: &[Bin]) -> [u8; 3] {
+    let mut sum = [0u64; 3];
+    let mut count = 0;
+    for (color, n) in bins {
+        for c in 0..3 {
+            sum[c] += u64::from(color[c]) * n;
+        }
+        count += n;
+    }
+    sum.map(|s| ((s as f64 / count as f64).round()) as u8)
+}
+
+fn median_cut(bins: Vec<Bin>, colors: usize) -> Vec<[u8; 3]> {
+    // Channel with the widest range of a box, along with that range
+    let widest = |bins: &[Bin]| {
+        (0..3)
+            .map(|c| {
+                let min = bins.iter().map(|(color, _)| color[c]).min().unwrap();
+                let max = bins.iter().map(|(color, _)| color[c]).max().unwrap();
+                (c, max - min)
+            })
+            .max_by_key(|&(c, range)| (range, std::cmp::Reverse(c)))
+            .unwrap()
+    };
+
+    let mut boxes = vec![bins];
+    while boxes.len() < colors {
+        let Some((i, channel)) = boxes
+            .iter()
+            .enumerate()
+            .filter(|(_, bins)| bins.len() > 1)
+            .map(|(i, bins)| (i, widest(bins)))
+            .max_by_key(|&(i, (_, range))| (range, std::cmp::Reverse(i)))
+            .map(|(i, (channel, _))| (i, channel))
+        else {
+            break;
+        };
+
+        let mut bins = boxes.swap_remove(i);
+        bins.sort_unstable_by_key(|(color, _)| color[channel]);
+
+        // Split where half of the pixels are on each side, keeping both
+        // halves non-empty
+        let total: u64 = bins.iter().map(|(_, n)| n).sum();
+        let mut below = 0;
+        let mut split = 1;
+        for (j, (_, n)) in bins.iter().enumerate() {
+            below += n;
+            if 2 * below >= total {
+                split = j + 1;
+                break;
+            }
+        }
+        let split = split.clamp(1, bins.len() - 1);
+
+        let upper = bins.split_off(split);
+        boxes.push(bins);
+        boxes.push(upper);
+    }
+
+    boxes.iter().map(|bins| mean(bins)).collect()
+}
+
+/// Node of the octree, with the sums of every color below it
+#[derive(Default)]
+struct Node {
+    children: [Option<usize>; 8],
+    sum: [u64; 3],
+    count: u64,
+    leaf: bool,
+}
+
+fn octree(bins: &[Bin], colors: usize) -> Vec<[u8; 3]> {
+    const DEPTH: usize = 8;
+
+    let mut nodes = vec![Node::default()];
+    let mut levels: Vec<Vec<usize>> = vec![Vec::new(); DEPTH];
+    levels[0].push(0);
+    let mut leaves = 0;
+
+    for &(color, n) in bins {
+        let mut node = 0;
+        for level in 0..=DEPTH {
+            nodes[node].count += n;
+            for (sum, c) in nodes[node].sum.iter_mut().zip(color) {
+                *sum += u64::from(c) * n;
+            }
+            if level == DEPTH {
+                nodes[node].leaf = true;
+                leaves += 1;
+                break;
+            }
+
+            let bit = 7 - level;
+            let child = (((color[0] >> bit) & 1) << 2
+                | ((color[1] >> bit) & 1) << 1
+                | ((color[2] >> bit) & 1)) as usize;
+            node = match nodes[node].children[child] {
+                Some(next) => next,
+                None => {
+                    nodes.push(Node::default());
+                    let next = nodes.len() - 1;
+                    nodes[node].children[child] = Some(next);
+                    if level + 1 < DEPTH {
+                        levels[level + 1].push(next);
+                    }
+                    next
+                }
+            };
+        }
+    }
+
+    // Deepest levels are merged first, so every child of a merged node is
+    // already a leaf. Within a level, the least used nodes go first
+    'merge: for level in (0..DEPTH).rev() {
+        let mut candidates = levels[level].clone();
+        candidates.sort_by_key(|&node| std::cmp::Reverse((nodes[node].count, node)));
+        while let Some(node) = candidates.pop() {
+            if leaves <= colors {
+                break 'merge;
+            }
+            let children = nodes[node].children.iter().flatten().count();
+            nodes[node].leaf = true;
+            leaves -= children - 1;
+        }
+    }
+
+    let mut picked = Vec::new();
+    let mut stack = vec![0];
+    while let Some(node) = stack.pop() {
+        let Node {
+            children,
+            sum,
+            count,
+            leaf,
+        } = &nodes[node];
+        if *leaf {
+            picked.push(sum.map(|s| (s as f64 / *count as f64).round() as u8));
+        } else {
+            stack.extend(children.iter().rev().flatten());
+        }
+    }
+    picked
+}
+
+fn k_means(bins: &[Bin], colors: usize, seed: u64) -> Vec<[u8; 3]> {
+    const ITERATIONS: usize = 32;
+
+    let points: Vec<[f64; 3]> = bins.iter().map(|(color, _)| color.map(f64::from)).collect();
+    let distance = |a: &[f64; 3], b: &[f64; 3]| (0..3).map(|c| (a[c] - b[c]).powi(2)).sum::<f64>();
+
+    // k-means++, each new center is drawn with a probability proportional to
+    // its squared distance to the closest center, times its use
+    let mut rng = Rng::new(seed);
+    let mut centers = vec![points[rng.below(points.len())]];
+    let mut closest: Vec<f64> = points.iter().map(|p| distance(p, &centers[0])).collect();
+    while centers.len() < colors {
+        let weights: Vec<f64> = closest
+            .iter()
+            .zip(bins)
+            .map(|(d, (_, n))| d * *n as f64)
+            .collect();
+        let total: f64 = weights.iter().sum();
+        if total == 0.0 {
+            break;
+        }
+
+        let mut target = rng.next_f64() * total;
+        let mut pick = weights.len() - 1;
+        for (i, w) in weights.iter().enumerate() {
+            if target < *w {
+                pick = i;
+                break;
+            }
+            target -= w;
+        }
+
+        let center = points[pick];
+        for (d, p) in closest.iter_mut().zip(&points) {
+            *d = d.min(distance(p, &center));
+        }
+        centers.push(center);
+    }
+
+    // Lloyd iterations, a center nothing is assigned to stays where it is
+    let mut assigned = vec![0; points.len()];
+    for iteration in 0..ITERATIONS {
+        let mut changed = false;
+        for (i, p) in points.iter().enumerate() {
+            let nearest = (0..centers.len())
+                .min_by(|&a, &b| distance(p, &centers[a]).total_cmp(&distance(p, &centers[b])))
+                .unwrap();
+            if nearest != assigned[i] || iteration == 0 {
+                changed = true;
+            }
+            assigned[i] = nearest;
+        }
+        if !changed {
+            break;
+        }
+
+        let mut sums = vec![([0.0; 3], 0.0); centers.len()];
+        for ((p, (_, n)), &center) in points.iter().zip(bins).zip(&assigned) {
+            let (sum, count) = &mut sums[center];
+            for c in 0..3 {
+                sum[c] += p[c] * *n as f64;
+            }
+            *count += *n as f64;
+        }
+        for (center, (sum, count)) in centers.iter_mut().zip(sums) {
+            if count > 0.0 {
+                *center = sum.map(|s| s / count);
+            }
+        }
+    }
+
+    centers
+        .into_iter()
+        .map(|center| center.map(|c| c.round() as u8))
+        .collect()
+}
+
+/// Side of Wu's histogram, 5 bits per channel plus an empty first slice
+const SIDE: usize = 33;
+
+/// Moments of a histogram cell: weight, sum of each channel, sum of squares
+type Moments = [f64; 5];
+
+/// Box of histogram cells, lower bounds excluded and upper bounds included
+#[derive(Debug, Clone, Copy)]
+struct Cube {
+    lower: [usize; 3],
+    upper: [usize; 3],
+}
+
+/// Moments of the cells inside `cube`, from cumulative `moments`
+fn volume(cube: &Cube, moments: &[Moments]) -> Moments {
+    let at = |r: usize, g: usize, b: usize| &moments[(r * SIDE + g) * SIDE + b];
+    let ([r0, g0, b0], [r1, g1, b1]) = (cube.lower, cube.upper);
+    let corners = [
+        (at(r1, g1, b1), 1.0),
+        (at(r1, g1, b0), -1.0),
+        (at(r1, g0, b1), -1.0),
+        (at(r1, g0, b0), 1.0),
+        (at(r0, g1, b1), -1.0),
+        (at(r0, g1, b0), 1.0),
+        (at(r0, g0, b1), 1.0),
+        (at(r0, g0, b0), -1.0),
+    ];
+
+    let mut sum = [0.0; 5];
+    for (m, sign) in corners {
+        for i in 0..5 {
+            sum[i] += sign * m[i];
+        }
+    }
+    sum
+}
+
+/// Squared length of the channel sums over the weight, which a cut maximizes
+fn spread([w, r, g, b, _]: Moments) -> f64 {
+    (r * r + g * g + b * b) / w
+}
+
+fn variance(cube: &Cube, moments: &[Moments]) -> f64 {
+    let m = volume(cube, moments);
+    m[4] - spread(m)
+}
+
+/// Best cut of `cube` along `channel`, with its score
+fn maximize(
+    cube: &Cube,
+    channel: usize,
+    whole: Moments,
+    moments: &[Moments],
+) -> Option<(f64, usize)> {
+    let mut best = None;
+    for cut in cube.lower[channel] + 1..cube.upper[channel] {
+        let mut half = *cube;
+        half.upper[channel] = cut;
+        let half = volume(&half, moments);
+        let other: Moments = std::array::from_fn(|i| whole[i] - half[i]);
+        if half[0] == 0.0 || other[0] == 0.0 {
+            continue;
+        }
+
+        let score = spread(half) + spread(other);
+        if best.is_none_or(|(max, _)| score > max) {
+            best = Some((score, cut));
+        }
+    }
+    best
+}
+
+fn wu(bins: &[Bin], colors: usize) -> Vec<[u8; 3]> {
+    let mut moments = vec![[0.0; 5]; SIDE * SIDE * SIDE];
+    for (color, n) in bins {
+        let [r, g, b] = color.map(|c| (c >> 3) as usize + 1);
+        let [cr, cg, cb] = color.map(f64::from);
+        let n = *n as f64;
+        let cell = &mut moments[(r * SIDE + g) * SIDE + b];
+        cell[0] += n;
+        cell[1] += cr * n;
+        cell[2] += cg * n;
+        cell[3] += cb * n;
+        cell[4] += (cr * cr + cg * cg + cb * cb) * n;
+    }
+
+    // Cumulative sums along each axis in turn
+    for axis in 0..3 {
+        let stride = SIDE.pow(2 - axis as u32);
+        for i in 0..moments.len() {
+            if !(i / stride).is_multiple_of(SIDE) {
+                let previous = moments[i - stride];
+                for (m, p) in moments[i].iter_mut().zip(previous) {
+                    *m += p;
+                }
+            }
+        }
+    }
+
+    let mut cubes = vec![Cube {
+        lower: [0; 3],
+        upper: [SIDE - 1; 3],
+    }];
+    let mut variances = vec![0.0];
+    let mut next = 0;
+    while cubes.len() < colors {
+        let cube = cubes[next];
+        let whole = volume(&cube, &moments);
+        let cut = (0..3)
+            .filter_map(|channel| {
+                maximize(&cube, channel, whole, &moments).map(|(score, at)| (score, channel, at))
+            })
+            .max_by(|a, b| a.0.total_cmp(&b.0));
+
+        match cut {
+            Some((_, channel, at)) => {
+                let mut lower = cube;
+                let mut upper = cube;
+                lower.upper[channel] = at;
+                upper.lower[channel] = at;
+                cubes[next] = lower;
+                cubes.push(upper);
+
+                for i in [next, cubes.len() - 1] {
+                    let weight = volume(&cubes[i], &moments)[0];
+                    let var = if weight > 1.0 {
+                        variance(&cubes[i], &moments)
+                    } else {
+                        0.0
+                    };
+                    if i < variances.len() {
+                        variances[i] = var;
+                    } else {
+                        variances.push(var);
+                    }
+                }
+            }
+            None => variances[next] = 0.0,
+        }
+
+        next = (0..cubes.len())
+            .max_by(|&a, &b| variances[a].total_cmp(&variances[b]).then(b.cmp(&a)))
+            .unwrap();
+        if variances[next] <= 0.0 {
+            break;
+        }
+    }
+
+    cubes
+        .iter()
+        .map(|cube| {
+            let [w, r, g, b, _] = volume(cube, &moments);
+            [r, g, b].map(|c| (c / w).round() as u8)
+        })
+        .collect()
+}
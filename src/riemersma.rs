@@ -0,0 +1,208 @@
+use image::{GrayImage, RgbImage, RgbaImage};
+use std::collections::VecDeque;
+
+use crate::target::{dither_gray, dither_palette, Target};
+use crate::{Ditherer, GrayLevels, Grayscale, Palette, Threshold};
+
+/// Riemersma dithering
+///
+/// Walks the image along a Hilbert curve and diffuses the error of the last
+/// `queue_len` pixels into the current one. Older errors weigh exponentially
+/// less, the newest one weighs `ratio` times as much as the oldest.
+/// Non square images are walked with a
+/// [generalized Hilbert curve](https://github.com/jakubcerveny/gilbert), so
+/// every step still moves to a neighbouring pixel
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Riemersma {
+    queue_len: usize,
+    ratio: f32,
+    threshold: Option<Threshold>,
+    grayscale: Grayscale,
+    linear: bool,
+}
+
+impl Default for Riemersma {
+    /// Queue of 16 pixels, newest error 16 times heavier than the oldest one,
+    /// as in the original article
+    fn default() -> Self {
+        Self::new(16, 16.0)
+    }
+}
+
+impl Riemersma {
+    /// ## Parameters
+    /// - `queue_len`: Number of past errors to remember, at least 1
+    /// - `ratio`: Weight of the newest error relative to the oldest one
+    pub fn new(queue_len: usize, ratio: f32) -> Self {
+        Self {
+            queue_len: queue_len.max(1),
+            ratio,
+            threshold: None,
+            grayscale: Grayscale::default(),
+            linear: true,
+        }
+    }
+
+    /// Sets where gray output switches to the next level before the error
+    /// is diffused, halfway between levels if None, the default
+    pub fn with_threshold(mut self, threshold: Option<Threshold>) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    /// Sets how pixels are turned into gray values for black and white
+    /// output, [`Grayscale::Rec709`] by default
+    pub fn with_grayscale(mut self, grayscale: Grayscale) -> Self {
+        self.grayscale = grayscale;
+        self
+    }
+
+    /// Sets whether error is diffused in linear light, on by default
+    pub fn with_linear(mut self, linear: bool) -> Self {
+        self.linear = linear;
+        self
+    }
+
+    pub fn threshold(&self) -> Option<Threshold> {
+        self.threshold
+    }
+
+    pub fn grayscale(&self) -> Grayscale {
+        self.grayscale
+    }
+
+    pub fn linear(&self) -> bool {
+        self.linear
+    }
+
+    /// Weights for each queue slot, oldest first, with the newest one at 1
+    fn weights(&self) -> Vec<f32> {
+        let steps = (self.queue_len - 1).max(1) as f32;
+        (0..self.queue_len)
+            .map(|i| self.ratio.powf(i as f32 / steps) / self.ratio)
+            .collect()
+    }
+}
+
+impl Riemersma {
+    /// Walks `values`, given in row-major order, and returns the index of the
+    /// target value chosen for each pixel
+    fn walk<const N: usize>(
+        &self,
+        w: u32,
+        h: u32,
+        values: &[[f32; N]],
+        target: &dyn Target<N>,
+    ) -> Vec<usize> {
+        let mut indices = vec![0; values.len()];
+
+        let weights = self.weights();
+        let mut history: VecDeque<[f32; N]> = vec![[0.0; N]; self.queue_len].into();
+
+        for (x, y) in hilbert_curve(w, h) {
+            let at = y as usize * w as usize + x as usize;
+            let old_pxl = values[at];
+            let error: [f32; N] =
+                std::array::from_fn(|c| history.iter().zip(&weights).map(|(e, w)| e[c] * w).sum());
+            let index = target.nearest(std::array::from_fn(|c| old_pxl[c] + error[c]));
+            let new_pxl = target.value(index);
+
+            history.pop_front();
+            history.push_back(std::array::from_fn(|c| old_pxl[c] - new_pxl[c]));
+
+            indices[at] = index;
+        }
+
+        indices
+    }
+}
+
+impl Ditherer for Riemersma {
+    fn dither_levels(&self, img: &RgbaImage, levels: &GrayLevels) -> GrayImage {
+        let (w, h) = img.dimensions();
+        dither_gray(
+            img,
+            levels,
+            self.grayscale,
+            self.linear,
+            self.threshold,
+            |values, target| self.walk(w, h, values, target),
+        )
+    }
+
+    fn dither_palette(&self, img: &RgbaImage, palette: &Palette) -> RgbImage {
+        let (w, h) = img.dimensions();
+        dither_palette(img, palette, self.linear, |values, target| {
+            self.walk(w, h, values, target)
+        })
+    }
+}
+
+/// Lists every pixel of a `w`x`h` image in generalized Hilbert curve order
+fn hilbert_curve(w: u32, h: u32) -> Vec<(u32, u32)> {
+    let mut points = Vec::with_capacity(w as usize * h as usize);
+    if w == 0 || h == 0 {
+        return points;
+    }
+
+    let (w, h) = (w as i64, h as i64);
+    if w >= h {
+        generate_curve(&mut points, (0, 0), (w, 0), (0, h));
+    } else {
+        generate_curve(&mut points, (0, 0), (0, h), (w, 0));
+    }
+
+    points
+}
+
+/// Appends the curve filling the rectangle at `start` spanned by the major
+/// axis `a` and the minor axis `b`
+fn generate_curve(points: &mut Vec<(u32, u32)>, start: (i64, i64), a: (i64, i64), b: (i64, i64)) {
+    let (x, y) = start;
+    let (ax, ay) = a;
+    let (bx, by) = b;
+
+    let w = (ax + ay).abs();
+    let h = (bx + by).abs();
+    let (dax, day) = (ax.signum(), ay.signum());
+    let (dbx, dby) = (bx.signum(), by.signum());
+
+    // A single row or column is walked straight
+    if h == 1 {
+        points.extend((0..w).map(|i| ((x + i * dax) as u32, (y + i * day) as u32)));
+        return;
+    }
+    if w == 1 {
+        points.extend((0..h).map(|i| ((x + i * dbx) as u32, (y + i * dby) as u32)));
+        return;
+    }
+
+    let (mut ax2, mut ay2) = (ax.div_euclid(2), ay.div_euclid(2));
+    let (mut bx2, mut by2) = (bx.div_euclid(2), by.div_euclid(2));
+    let w2 = (ax2 + ay2).abs();
+    let h2 = (bx2 + by2).abs();
+
+    if 2 * w > 3 * h {
+        // Long rectangle, split it in two along the major axis
+        if w2 % 2 == 1 && w > 2 {
+            ax2 += dax;
+            ay2 += day;
+        }
+        generate_curve(points, (x, y), (ax2, ay2), b);
+        generate_curve(points, (x + ax2, y + ay2), (ax - ax2, ay - ay2), b);
+    } else {
+        // Standard Hilbert step: up, across and back down
+        if h2 % 2 == 1 && h > 2 {
+            bx2 += dbx;
+            by2 += dby;
+        }
+        generate_curve(points, (x, y), (bx2, by2), (ax2, ay2));
+        generate_curve(points, (x + bx2, y + by2), a, (bx - bx2, by - by2));
+        generate_curve(
+            points,
+            (x + (ax - dax) + (bx2 - dbx), y + (ay - day) + (by2 - dby)),
+            (-bx2, -by2),
+            (-(ax - ax2), -(ay - ay2)),
+        );
+    }
+}
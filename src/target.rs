@@ -0,0 +1,92 @@
+use image::{GrayImage, ImageBuffer, Luma, RgbImage, Rgba, RgbaImage};
+
+use crate::color::{linear_to_srgb, srgb_to_linear};
+use crate::{GrayLevels, Grayscale, Palette, Threshold};
+
+/// Output values a ditherer picks from for each pixel, with `N` channels
+/// between 0 and 1. Lets every algorithm share one implementation between
+/// gray level and palette output
+pub(crate) trait Target<const N: usize> {
+    /// Index of the value closest to `value`
+    fn nearest(&self, value: [f32; N]) -> usize;
+
+    /// Value at `index`
+    fn value(&self, index: usize) -> [f32; N];
+
+    /// Index of the value an ordered ditherer picks for `value`, at a
+    /// position where the threshold map holds `threshold`, between 0 and 1.
+    /// A threshold of 0.5 picks about the same value as [`Target::nearest`]
+    fn ordered(&self, value: [f32; N], threshold: f32) -> usize;
+}
+
+/// Gray level of `value` as stored in the image, between 0 and 1, with
+/// colors averaged
+///
+/// ## Parameters
+/// - `linear`: Whether `value` is in linear light
+pub(crate) fn stored_gray<const N: usize>(value: [f32; N], linear: bool) -> f32 {
+    let mean = value.iter().sum::<f32>() / N as f32;
+    if linear {
+        linear_to_srgb(mean)
+    } else {
+        mean
+    }
+}
+
+/// Runs `dither` on the gray values of `img`, in row-major order, and builds
+/// the image from the level indices it returns
+///
+/// ## Parameters
+/// - `levels`: Gray levels the output may use
+/// - `grayscale`: How each pixel is turned into a gray value
+/// - `linear`: Take gray values in linear light, so error is measured in
+///   linear light
+/// - `threshold`: Where the output switches from black to white, halfway
+///   if None
+pub(crate) fn dither_gray(
+    img: &RgbaImage,
+    levels: &GrayLevels,
+    grayscale: Grayscale,
+    linear: bool,
+    threshold: Option<Threshold>,
+    dither: impl FnOnce(&[[f32; 1]], &dyn Target<1>) -> Vec<usize>,
+) -> GrayImage {
+    let values: Vec<[f32; 1]> = img.pixels().map(|p| [grayscale.gray(p, linear)]).collect();
+    let cut = threshold.map(|threshold| threshold.resolve(&values, linear));
+    let indices = dither(&values, &levels.target(linear, cut));
+
+    let (w, h) = img.dimensions();
+    ImageBuffer::from_fn(w, h, |x, y| {
+        Luma([levels.level(indices[y as usize * w as usize + x as usize])])
+    })
+}
+
+/// Runs `dither` on the color channels of `img`, in row-major order, and
+/// builds the image from the palette indices it returns
+///
+/// ## Parameters
+/// - `linear`: Decode channels from sRGB, so error is measured in linear light
+pub(crate) fn dither_palette(
+    img: &RgbaImage,
+    palette: &Palette,
+    linear: bool,
+    dither: impl FnOnce(&[[f32; 3]], &dyn Target<3>) -> Vec<usize>,
+) -> RgbImage {
+    let decode = |c: u8| {
+        let v = f32::from(c) / 255.0;
+        if linear {
+            srgb_to_linear(v)
+        } else {
+            v
+        }
+    };
+    let values: Vec<[f32; 3]> = img
+        .pixels()
+        .map(|Rgba([r, g, b, _])| [*r, *g, *b].map(decode))
+        .collect();
+
+    let indices = dither(&values, &palette.matcher(true, linear));
+
+    let (w, h) = img.dimensions();
+    palette.image(w, h, &indices)
+}
@@ -0,0 +1,181 @@
+use image::{GrayImage, RgbImage, RgbaImage};
+
+use crate::color::{linear_to_srgb, srgb_to_linear};
+use crate::target::{dither_gray, dither_palette, Target};
+use crate::{Ditherer, GrayLevels, Grayscale, Palette};
+
+/// Gray value where the output switches from black to white
+///
+/// Without one, pixels go to the nearest level, so the cut sits halfway
+/// between black and white in the space dithering works in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Threshold {
+    /// A gray value between 0 and 255, as stored in the image
+    Fixed(u8),
+    /// [Otsu's method](https://en.wikipedia.org/wiki/Otsu%27s_method), the
+    /// cut that best separates the image into a dark and a light class
+    Otsu,
+    /// Mean gray value of the image
+    Mean,
+    /// Median gray value of the image, so half the pixels fall on each side
+    Median,
+}
+
+impl Threshold {
+    /// Looks up an automatic threshold by name, `otsu`, `mean` or `median`
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "otsu" => Some(Threshold::Otsu),
+            "mean" => Some(Threshold::Mean),
+            "median" => Some(Threshold::Median),
+            _ => None,
+        }
+    }
+
+    /// Picks the cut for the gray `values` of an image, between 0 and 1
+    ///
+    /// Every threshold is picked among the gray values as stored in the
+    /// image, like [`Threshold::Fixed`], then moved to the space of `values`
+    ///
+    /// ## Parameters
+    /// - `values`: Gray values between 0 and 1, in any order
+    /// - `linear`: Whether `values` are in linear light
+    pub(crate) fn resolve(self, values: &[[f32; 1]], linear: bool) -> f32 {
+        let stored: Vec<f32> = values
+            .iter()
+            .map(|&[v]| if linear { linear_to_srgb(v) } else { v })
+            .collect();
+        let cut = match self {
+            _ if stored.is_empty() => 0.5,
+            Threshold::Fixed(gray) => f32::from(gray) / 255.0,
+            Threshold::Mean => stored.iter().sum::<f32>() / stored.len() as f32,
+            Threshold::Median => {
+                let mut sorted = stored;
+                let middle = sorted.len() / 2;
+                *sorted.select_nth_unstable_by(middle, f32::total_cmp).1
+            }
+            Threshold::Otsu => otsu(&stored),
+        };
+        if linear {
+            srgb_to_linear(cut)
+        } else {
+            cut
+        }
+    }
+}
+
+/// Splits a 256 bin histogram of `values` where the variance between the
+/// two classes is the largest
+fn otsu(values: &[f32]) -> f32 {
+    const BINS: usize = 256;
+    let mut histogram = [0u64; BINS];
+    for v in values {
+        histogram[(v.clamp(0.0, 1.0) * (BINS - 1) as f32).round() as usize] += 1;
+    }
+
+    let total = values.len() as f64;
+    let sum: f64 = histogram
+        .iter()
+        .enumerate()
+        .map(|(bin, &count)| bin as f64 * count as f64)
+        .sum();
+
+    let (mut best, mut best_variance) = (0, -1.0);
+    let (mut dark, mut dark_sum) = (0.0, 0.0);
+    for (bin, &count) in histogram.iter().enumerate() {
+        dark += count as f64;
+        dark_sum += bin as f64 * count as f64;
+        let light = total - dark;
+        if dark == 0.0 || light == 0.0 {
+            continue;
+        }
+        let mean_difference = dark_sum / dark - (sum - dark_sum) / light;
+        let variance = dark * light * mean_difference * mean_difference;
+        if variance > best_variance {
+            best = bin;
+            best_variance = variance;
+        }
+    }
+
+    // Halfway to the next bin, so the whole dark class stays below the cut
+    (best as f32 + 0.5) / (BINS - 1) as f32
+}
+
+/// Plain thresholding, every pixel goes to its nearest level or palette color
+/// and the error is dropped
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Thresholding {
+    threshold: Option<Threshold>,
+    grayscale: Grayscale,
+    linear: bool,
+}
+
+impl Default for Thresholding {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Thresholding {
+    pub fn new() -> Self {
+        Self {
+            threshold: None,
+            grayscale: Grayscale::default(),
+            linear: true,
+        }
+    }
+
+    /// Sets where gray output switches to the next level, halfway between
+    /// levels if None, the default
+    pub fn with_threshold(mut self, threshold: Option<Threshold>) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    /// Sets how pixels are turned into gray values for black and white
+    /// output, [`Grayscale::Rec709`] by default
+    pub fn with_grayscale(mut self, grayscale: Grayscale) -> Self {
+        self.grayscale = grayscale;
+        self
+    }
+
+    /// Sets whether pixels are compared in linear light, on by default
+    pub fn with_linear(mut self, linear: bool) -> Self {
+        self.linear = linear;
+        self
+    }
+
+    pub fn threshold(&self) -> Option<Threshold> {
+        self.threshold
+    }
+
+    pub fn grayscale(&self) -> Grayscale {
+        self.grayscale
+    }
+
+    pub fn linear(&self) -> bool {
+        self.linear
+    }
+}
+
+/// Index of the nearest target value of every pixel
+fn nearest<const N: usize>(values: &[[f32; N]], target: &dyn Target<N>) -> Vec<usize> {
+    values.iter().map(|value| target.nearest(*value)).collect()
+}
+
+impl Ditherer for Thresholding {
+    fn dither_levels(&self, img: &RgbaImage, levels: &GrayLevels) -> GrayImage {
+        dither_gray(
+            img,
+            levels,
+            self.grayscale,
+            self.linear,
+            self.threshold,
+            nearest,
+        )
+    }
+
+    fn dither_palette(&self, img: &RgbaImage, palette: &Palette) -> RgbImage {
+        dither_palette(img, palette, self.linear, nearest)
+    }
+}
@@ -0,0 +1,106 @@
+use dithering::{composite, Alpha, Ditherer, Palette};
+use image::{Rgb, Rgba, RgbaImage};
+
+mod common;
+
+use common::floyd;
+
+/// Red, fading from transparent on the left to opaque on the right
+fn fade() -> RgbaImage {
+    RgbaImage::from_fn(64, 16, |x, _| Rgba([220, 40, 40, (x * 4) as u8]))
+}
+
+#[test]
+fn composites_over_the_background() {
+    let img = RgbaImage::from_fn(3, 1, |x, _| Rgba([200, 100, 0, [0, 128, 255][x as usize]]));
+    let blended = composite(&img, Rgb([0, 0, 255]));
+    assert_eq!(
+        blended.pixels().copied().collect::<Vec<_>>(),
+        [
+            Rgba([0, 0, 255, 255]),
+            Rgba([147, 72, 187, 255]),
+            Rgba([200, 100, 0, 255])
+        ]
+    );
+
+    // Mixed in linear light, half covered white reflects half the light
+    let half = RgbaImage::from_pixel(1, 1, Rgba([255, 255, 255, 128]));
+    let blended = composite(&half, Rgb([0, 0, 0]));
+    assert_eq!(*blended.get_pixel(0, 0), Rgba([188, 188, 188, 255]));
+}
+
+#[test]
+fn transparent_areas_dither_as_the_background() {
+    // Fully transparent black, as many encoders store it
+    let img = RgbaImage::from_pixel(16, 16, Rgba([0, 0, 0, 0]));
+    let dithered = floyd().dither(&composite(&img, Rgb([255, 255, 255])));
+    assert!(dithered.pixels().all(|p| p.0[0] == 255));
+}
+
+#[test]
+fn keeps_alpha() {
+    let img = fade();
+    let alpha = Alpha::Keep.channel(&img);
+    assert!(img.pixels().zip(alpha.pixels()).all(|(p, a)| p[3] == a[0]));
+}
+
+#[test]
+fn thresholds_alpha() {
+    let alpha = Alpha::Threshold(100).channel(&fade());
+    for (x, _, a) in alpha.enumerate_pixels() {
+        let expected = if x * 4 >= 100 { 255 } else { 0 };
+        assert_eq!(a[0], expected, "x {}", x);
+    }
+}
+
+#[test]
+fn dithers_alpha_into_a_mask() {
+    let img = RgbaImage::from_pixel(64, 64, Rgba([0, 0, 0, 64]));
+    let mask = Alpha::Dither.channel(&img);
+    assert!(mask.pixels().all(|a| a[0] == 0 || a[0] == 255));
+    let opaque = mask.pixels().filter(|a| a[0] == 255).count() as f32 / mask.len() as f32;
+    assert!((opaque - 64.0 / 255.0).abs() < 0.02, "{}", opaque);
+
+    // Opacity grows along the fade
+    let mask = Alpha::Dither.channel(&fade());
+    let opaque = |x0: u32| {
+        (x0..x0 + 16)
+            .flat_map(|x| (0..16).map(move |y| (x, y)))
+            .filter(|&(x, y)| mask.get_pixel(x, y)[0] == 255)
+            .count()
+    };
+    assert!(opaque(0) < opaque(16) && opaque(16) < opaque(32) && opaque(32) < opaque(48));
+}
+
+#[test]
+fn outputs_gray_and_alpha() {
+    let img = fade();
+    let dithered = floyd().dither(&img);
+    let output = Alpha::Threshold(128).apply(&img, &dithered);
+
+    assert_eq!(output.dimensions(), img.dimensions());
+    for ((x, y, p), d) in output.enumerate_pixels().zip(dithered.pixels()) {
+        let opaque = img.get_pixel(x, y)[3] >= 128;
+        assert_eq!(p.0, [d[0], if opaque { 255 } else { 0 }]);
+    }
+}
+
+#[test]
+fn outputs_colors_and_alpha() {
+    let img = fade();
+    let palette = Palette::from_hex("#000000,#ff0000,#ffffff").unwrap();
+    let dithered = floyd().dither_palette(&img, &palette);
+    let output = Alpha::Keep.apply_rgb(&img, &dithered);
+
+    for ((p, d), source) in output.pixels().zip(dithered.pixels()).zip(img.pixels()) {
+        assert_eq!(p.0, [d[0], d[1], d[2], source[3]]);
+    }
+}
+
+#[test]
+fn looks_up_names() {
+    assert_eq!(Alpha::from_name("keep"), Some(Alpha::Keep));
+    assert_eq!(Alpha::from_name("threshold"), Some(Alpha::Threshold(128)));
+    assert_eq!(Alpha::from_name("dither"), Some(Alpha::Dither));
+    assert_eq!(Alpha::from_name("premultiply"), None);
+}
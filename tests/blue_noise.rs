@@ -0,0 +1,68 @@
+use dithering::ThresholdMap;
+
+fn ranks(map: &ThresholdMap) -> Vec<usize> {
+    let (w, h) = map.dimensions();
+    let count = (w * h) as f32;
+    (0..w * h)
+        .map(|i| (map.threshold(i % w, i / w) * count) as usize)
+        .collect()
+}
+
+#[test]
+fn is_deterministic() {
+    let a = ThresholdMap::blue_noise(16, 7).unwrap();
+    assert_eq!(a, ThresholdMap::blue_noise(16, 7).unwrap());
+    assert_ne!(a, ThresholdMap::blue_noise(16, 8).unwrap());
+}
+
+#[test]
+fn uses_every_level_once() {
+    for size in [1, 2, 5, 16] {
+        let mut ranks = ranks(&ThresholdMap::blue_noise(size, 0).unwrap());
+        ranks.sort_unstable();
+        assert!(
+            ranks.into_iter().eq(0..(size * size) as usize),
+            "size {}",
+            size
+        );
+    }
+}
+
+#[test]
+fn sparse_levels_are_evenly_spread() {
+    let size = 32;
+    let ranks = ranks(&ThresholdMap::blue_noise(size, 0).unwrap());
+
+    // The darkest 1/16 of the thresholds should never touch each other
+    let points: Vec<(i32, i32)> = (0..size * size)
+        .filter(|&i| ranks[i as usize] < (size * size / 16) as usize)
+        .map(|i| ((i % size) as i32, (i / size) as i32))
+        .collect();
+    for (i, a) in points.iter().enumerate() {
+        for b in &points[i + 1..] {
+            let dx = (a.0 - b.0).rem_euclid(size as i32);
+            let dy = (a.1 - b.1).rem_euclid(size as i32);
+            let dx = dx.min(size as i32 - dx);
+            let dy = dy.min(size as i32 - dy);
+            assert!(dx > 1 || dy > 1, "{:?} and {:?} are neighbours", a, b);
+        }
+    }
+}
+
+#[test]
+fn cache_round_trips() {
+    let dir = std::env::temp_dir().join(format!("dithering-blue-noise-{}", std::process::id()));
+    let generated = ThresholdMap::blue_noise_cached(16, 3, &dir).unwrap();
+    let cached = ThresholdMap::blue_noise_cached(16, 3, &dir).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(ranks(&generated), ranks(&cached));
+    assert_eq!(generated, ThresholdMap::blue_noise(16, 3).unwrap());
+}
+
+#[test]
+fn cache_rejects_empty_masks() {
+    let dir = std::env::temp_dir().join(format!("dithering-blue-noise-0-{}", std::process::id()));
+    assert!(ThresholdMap::blue_noise_cached(0, 3, &dir).is_err());
+    assert!(!dir.exists());
+}
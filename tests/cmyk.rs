@@ -0,0 +1,93 @@
+use dithering::{CmykHalftone, Halftone};
+use image::{GrayImage, Rgb, Rgba, RgbaImage};
+
+mod common;
+
+use common::flat;
+
+fn separator() -> CmykHalftone {
+    // 10 pixel cells
+    CmykHalftone::new(Halftone::new(30.0, 300.0))
+}
+
+fn ink_fraction(img: &GrayImage) -> f32 {
+    let ink = img.pixels().filter(|p| p.0[0] == 0).count();
+    ink as f32 / (img.width() * img.height()) as f32
+}
+
+fn close(a: [f32; 4], b: [f32; 4]) -> bool {
+    a.iter().zip(b).all(|(a, b)| (a - b).abs() < 1e-3)
+}
+
+#[test]
+fn converts_to_ink_coverage() {
+    let cmyk = separator();
+    assert!(close(cmyk.to_cmyk(&Rgba([255, 255, 255, 255])), [0.0; 4]));
+    assert!(close(
+        cmyk.to_cmyk(&Rgba([0, 0, 0, 255])),
+        [0.0, 0.0, 0.0, 1.0]
+    ));
+    assert!(close(
+        cmyk.to_cmyk(&Rgba([255, 0, 0, 255])),
+        [0.0, 1.0, 1.0, 0.0]
+    ));
+    // Dark red: the shared gray goes to black, red stays in magenta and yellow
+    assert!(close(
+        cmyk.to_cmyk(&Rgba([128, 0, 0, 255])),
+        [0.0, 1.0, 1.0, 0.498]
+    ));
+}
+
+#[test]
+fn gcr_controls_black_generation() {
+    let gray = Rgba([102, 102, 102, 255]);
+    assert!(close(separator().to_cmyk(&gray), [0.0, 0.0, 0.0, 0.6]));
+    assert!(close(
+        separator().with_gcr(0.0).to_cmyk(&gray),
+        [0.6, 0.6, 0.6, 0.0]
+    ));
+    assert!(close(
+        separator().with_gcr(0.5).to_cmyk(&gray),
+        [0.4286, 0.4286, 0.4286, 0.3]
+    ));
+}
+
+#[test]
+fn separations_follow_ink_coverage() {
+    // 40% cyan, 20% magenta, no yellow, no black
+    let img = RgbaImage::from_pixel(120, 120, Rgba([153, 204, 255, 255]));
+    let separation = separator().separate(&img);
+
+    assert!((ink_fraction(&separation.cyan) - 0.4).abs() < 0.01);
+    assert!((ink_fraction(&separation.magenta) - 0.2).abs() < 0.01);
+    assert_eq!(ink_fraction(&separation.yellow), 0.0);
+    assert_eq!(ink_fraction(&separation.black), 0.0);
+}
+
+#[test]
+fn inks_use_their_own_screen_angle() {
+    let img = flat(60, 60, 128);
+    let separation = separator()
+        .with_gcr(0.0)
+        .with_angles([15.0, 75.0, 0.0, 45.0])
+        .separate(&img);
+
+    assert_ne!(separation.cyan, separation.magenta);
+    assert_ne!(separation.cyan, separation.yellow);
+    assert_ne!(separation.magenta, separation.yellow);
+}
+
+#[test]
+fn preview_mixes_inks() {
+    let img = RgbaImage::from_fn(2, 1, |x, _| {
+        if x == 0 {
+            Rgba([255, 255, 255, 255])
+        } else {
+            Rgba([0, 0, 255, 255])
+        }
+    });
+    let preview = separator().separate(&img).preview();
+
+    assert_eq!(preview.get_pixel(0, 0), &Rgb([255, 255, 255]));
+    assert_eq!(preview.get_pixel(1, 0), &Rgb([0, 0, 255]));
+}
@@ -0,0 +1,29 @@
+//! Fixtures shared by the integration tests
+
+// Every test crate compiles its own copy and only uses some of them
+#![allow(dead_code)]
+
+use dithering::{DiffusionKernel, ErrorDiffusion};
+use image::{GrayImage, Rgba, RgbaImage};
+
+pub fn floyd() -> ErrorDiffusion {
+    ErrorDiffusion::new(DiffusionKernel::FLOYD_STEINBERG)
+}
+
+/// `w`x`h` image of a single gray
+pub fn flat(w: u32, h: u32, value: u8) -> RgbaImage {
+    RgbaImage::from_pixel(w, h, Rgba([value, value, value, 255]))
+}
+
+/// `w`x`h` gray ramp, darkest at the top left
+pub fn gradient(w: u32, h: u32) -> RgbaImage {
+    RgbaImage::from_fn(w, h, |x, y| {
+        let v = (x * 3 + y) as u8;
+        Rgba([v, v, v, 255])
+    })
+}
+
+/// Share of white pixels in `img`
+pub fn white_fraction(img: &GrayImage) -> f32 {
+    img.pixels().filter(|p| p.0[0] == 255).count() as f32 / img.len() as f32
+}
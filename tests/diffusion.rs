@@ -0,0 +1,204 @@
+use dithering::color::srgb_to_linear;
+use dithering::{
+    DiffusionKernel, Ditherer, EdgeMode, ErrorDiffusion, Palette, ScanOrder, Tap, Thresholding,
+};
+use image::{GrayImage, Rgba, RgbaImage};
+
+mod common;
+
+use common::{flat, floyd, gradient, white_fraction};
+
+fn differences(a: &GrayImage, b: &GrayImage) -> usize {
+    a.pixels().zip(b.pixels()).filter(|(a, b)| a != b).count()
+}
+
+#[test]
+fn strength_scales_the_error() {
+    let img = gradient(64, 48);
+    let plain = Thresholding::new().dither(&img);
+    assert_eq!(floyd().with_strength(0.0).dither(&img), plain);
+    assert_eq!(
+        floyd().with_strength(1.0).dither(&img),
+        floyd().dither(&img)
+    );
+
+    // The more error is diffused, the further from plain thresholding
+    let half = differences(&floyd().with_strength(0.5).dither(&img), &plain);
+    let full = differences(&floyd().dither(&img), &plain);
+    assert!(0 < half && half < full, "{} {}", half, full);
+
+    assert_eq!(floyd().with_strength(2.0).strength(), 1.0);
+    assert_eq!(floyd().with_strength(-1.0).strength(), 0.0);
+}
+
+#[test]
+fn clamps_accumulated_values() {
+    // About 0.3 in linear light, always pulled up to 0.6
+    let img = flat(32, 32, 149);
+    let dithered = floyd().with_clamp(Some((0.6, 1.0))).unwrap().dither(&img);
+    assert!(dithered.pixels().all(|p| p.0[0] == 255));
+
+    // Values already inside the range are left alone
+    assert_eq!(
+        floyd()
+            .with_clamp(Some((-1.0, 2.0)))
+            .unwrap()
+            .dither(&gradient(64, 48)),
+        floyd().dither(&gradient(64, 48))
+    );
+}
+
+#[test]
+fn rejects_invalid_clamp_ranges() {
+    assert!(floyd().with_clamp(Some((1.0, 0.0))).is_none());
+    assert!(floyd().with_clamp(Some((f32::NAN, 1.0))).is_none());
+    assert!(floyd().with_clamp(Some((0.0, f32::NAN))).is_none());
+    let single = floyd().with_clamp(Some((0.5, 0.5))).unwrap();
+    assert_eq!(single.clamp(), Some((0.5, 0.5)));
+    assert_eq!(floyd().with_clamp(None).unwrap().clamp(), None);
+}
+
+#[test]
+fn clamping_stops_error_from_bleeding() {
+    // No mix of these colors gives saturated red, so its error keeps growing
+    // and tints the gray next to it
+    let img = RgbaImage::from_fn(64, 32, |x, _| {
+        if x < 48 {
+            Rgba([255, 0, 0, 255])
+        } else {
+            Rgba([188, 188, 188, 255])
+        }
+    });
+    let palette = Palette::from_hex("#000000,#00ffff,#ff00ff,#ffff00,#ffffff").unwrap();
+    let red_tint = |ditherer: ErrorDiffusion| {
+        let dithered = ditherer.dither_palette(&img, &palette);
+        let tint: i32 = (48..64)
+            .flat_map(|x| (0..32).map(move |y| (x, y)))
+            .map(|(x, y)| {
+                let [r, g, b] = dithered.get_pixel(x, y).0.map(i32::from);
+                r - (g + b) / 2
+            })
+            .sum();
+        tint as f32 / (16.0 * 32.0 * 255.0)
+    };
+    let clamped = red_tint(floyd().with_clamp(Some((0.0, 1.0))).unwrap());
+    let free = red_tint(floyd());
+    assert!(clamped.abs() < 0.05, "clamped: {}", clamped);
+    assert!(free > 0.1, "free: {}", free);
+}
+
+#[test]
+fn redistributes_error_at_the_edges() {
+    // A single column, where most Floyd-Steinberg taps fall outside
+    let img = flat(1, 400, 150);
+    let light = srgb_to_linear(150.0 / 255.0);
+
+    let kept = white_fraction(&floyd().with_edges(EdgeMode::Redistribute).dither(&img));
+    assert!((kept - light).abs() < 0.01, "redistribute: {}", kept);
+    let dropped = white_fraction(&floyd().with_edges(EdgeMode::Drop).dither(&img));
+    assert!((dropped - light).abs() > 0.05, "drop: {}", dropped);
+}
+
+#[test]
+fn edge_modes_agree_away_from_the_edges() {
+    let img = gradient(64, 48);
+    assert_eq!(floyd().edges(), EdgeMode::Drop);
+    let dropped = floyd().dither(&img);
+    let kept = floyd().with_edges(EdgeMode::Redistribute).dither(&img);
+    assert_ne!(dropped, kept);
+    let changed = differences(&dropped, &kept);
+    assert!(changed < dropped.len() / 5, "{} changed", changed);
+}
+
+#[test]
+fn looks_up_edge_modes() {
+    assert_eq!(EdgeMode::from_name("drop"), Some(EdgeMode::Drop));
+    assert_eq!(
+        EdgeMode::from_name("redistribute"),
+        Some(EdgeMode::Redistribute)
+    );
+    assert_eq!(EdgeMode::from_name("wrap"), None);
+}
+
+/// Right and down taps, sending more of the error down the lighter the pixel
+const SHIFT_TAPS: [Tap; 2] = [Tap::new(1, 0, 0.5), Tap::new(0, 1, 0.5)];
+
+const SHIFT_WEIGHTS: [f32; 256 * 2] = {
+    let mut weights = [0.0; 256 * 2];
+    let mut gray = 0;
+    while gray < 256 {
+        let down = gray as f32 / 255.0;
+        weights[gray * 2] = 1.0 - down;
+        weights[gray * 2 + 1] = down;
+        gray += 1;
+    }
+    weights
+};
+
+fn shifting() -> DiffusionKernel {
+    DiffusionKernel::variable(&SHIFT_TAPS, &SHIFT_WEIGHTS)
+}
+
+#[test]
+fn variable_weights_follow_the_gray_level() {
+    let kernel = shifting();
+    assert!(kernel.is_variable());
+    assert_eq!(&*kernel.weights(0), &[1.0, 0.0]);
+    assert_eq!(&*kernel.weights(255), &[0.0, 1.0]);
+    assert_eq!(&*kernel.weights(51), &[0.8, 0.2]);
+
+    let floyd = DiffusionKernel::FLOYD_STEINBERG;
+    assert!(!floyd.is_variable());
+    assert_eq!(&*floyd.weights(30), &[7.0, 3.0, 5.0, 1.0]);
+}
+
+#[test]
+fn ostromoukhov_uses_the_published_weights() {
+    let kernel = DiffusionKernel::OSTROMOUKHOV;
+    assert!(kernel.is_variable());
+    let expected = [
+        (0, [13.0 / 18.0, 0.0, 5.0 / 18.0]),
+        (10, [7.0 / 13.0, 3.0 / 13.0, 3.0 / 13.0]),
+        (22, [3.0 / 6.0, 2.0 / 6.0, 1.0 / 6.0]),
+        (32, [20.0 / 49.0, 10.0 / 49.0, 19.0 / 49.0]),
+        (44, [177.0 / 392.0, 120.0 / 392.0, 95.0 / 392.0]),
+        (64, [11.0 / 21.0, 10.0 / 21.0, 0.0]),
+        (72, [5.0 / 13.0, 7.0 / 13.0, 1.0 / 13.0]),
+        (80, [4.0 / 6.0, 1.0 / 6.0, 1.0 / 6.0]),
+        (98, [5.0 / 10.0, 3.0 / 10.0, 2.0 / 10.0]),
+        (127, [4.0 / 6.0, 1.0 / 6.0, 1.0 / 6.0]),
+    ];
+    for (gray, weights) in expected {
+        assert_eq!(&*kernel.weights(gray), &weights, "{}", gray);
+        assert_eq!(&*kernel.weights(255 - gray), &weights, "{}", gray);
+    }
+    for gray in 0..=255 {
+        let sum: f32 = kernel.weights(gray).iter().sum();
+        assert!((sum - 1.0).abs() < 1e-5, "{}: {}", gray, sum);
+    }
+    assert_eq!(DiffusionKernel::from_name("ostromoukhov"), Some(kernel));
+}
+
+#[test]
+fn variable_kernels_scan_serpentine() {
+    let variable = ErrorDiffusion::new(shifting());
+    assert_eq!(variable.scan(), ScanOrder::Serpentine);
+    let ostromoukhov = ErrorDiffusion::new(DiffusionKernel::OSTROMOUKHOV);
+    assert_eq!(ostromoukhov.scan(), ScanOrder::Serpentine);
+    assert_eq!(floyd().scan(), ScanOrder::Raster);
+
+    // Same taps with the weights fixed at mid-gray
+    let fixed = ErrorDiffusion::new(DiffusionKernel::from_static(&SHIFT_TAPS, 1.0))
+        .with_scan(ScanOrder::Serpentine);
+    for value in [20, 60, 120, 188, 230] {
+        let img = flat(64, 64, value);
+        let light = srgb_to_linear(f32::from(value) / 255.0);
+        let dithered = variable.dither(&img);
+        let white = white_fraction(&dithered);
+        assert!((white - light).abs() < 0.02, "{}: {}", value, white);
+        assert_ne!(dithered, fixed.dither(&img), "{}", value);
+
+        let white = white_fraction(&ostromoukhov.dither(&img));
+        assert!((white - light).abs() < 0.02, "{}: {}", value, white);
+    }
+}
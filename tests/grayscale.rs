@@ -0,0 +1,136 @@
+use dithering::{
+    luminance, luminosity, DiffusionKernel, Ditherer, ErrorDiffusion, Grayscale, Halftone, Ordered,
+    Riemersma,
+};
+use image::{Rgba, RgbaImage};
+
+const METHODS: [Grayscale; 8] = [
+    Grayscale::Rec601,
+    Grayscale::Rec709,
+    Grayscale::CieLightness,
+    Grayscale::HslLightness,
+    Grayscale::Average,
+    Grayscale::Red,
+    Grayscale::Green,
+    Grayscale::Blue,
+];
+
+fn gray(method: Grayscale, rgb: [u8; 3]) -> f32 {
+    method.gray(&Rgba([rgb[0], rgb[1], rgb[2], 255]), false)
+}
+
+#[test]
+fn rec709_matches_luminosity_and_luminance() {
+    for rgb in [[0, 0, 0], [200, 90, 40], [12, 250, 128], [255, 255, 255]] {
+        let pixel = Rgba([rgb[0], rgb[1], rgb[2], 255]);
+        assert_eq!(
+            Grayscale::Rec709.gray(&pixel, false),
+            luminosity(&pixel) / 255.0
+        );
+        assert_eq!(Grayscale::Rec709.gray(&pixel, true), luminance(&pixel));
+    }
+    assert_eq!(Grayscale::default(), Grayscale::Rec709);
+}
+
+#[test]
+fn keeps_grays() {
+    // L* spaces grays close to, but not exactly like, sRGB
+    for method in METHODS
+        .into_iter()
+        .filter(|&m| m != Grayscale::CieLightness)
+    {
+        for v in [0, 64, 128, 255] {
+            assert!(
+                (gray(method, [v; 3]) - f32::from(v) / 255.0).abs() < 0.01,
+                "{:?} on {}",
+                method,
+                v
+            );
+        }
+    }
+}
+
+#[test]
+fn weighs_channels() {
+    let orange = [255, 128, 0];
+    let close = |a: f32, b: f32| (a - b).abs() < 1e-3;
+    assert!(close(
+        gray(Grayscale::Rec601, orange),
+        0.299 + 0.587 * 128.0 / 255.0
+    ));
+    assert!(close(
+        gray(Grayscale::Average, orange),
+        (1.0 + 128.0 / 255.0) / 3.0
+    ));
+    assert!(close(gray(Grayscale::HslLightness, orange), 0.5));
+    assert!(close(gray(Grayscale::Red, orange), 1.0));
+    assert!(close(gray(Grayscale::Green, orange), 128.0 / 255.0));
+    assert!(close(gray(Grayscale::Blue, orange), 0.0));
+    // Orange has an L* of about 67
+    assert!(close(gray(Grayscale::CieLightness, orange), 0.6705));
+
+    let custom = Grayscale::custom([2.0, 0.0, 2.0]).unwrap();
+    assert_eq!(custom, Grayscale::Custom([0.5, 0.0, 0.5]));
+    assert!(close(gray(custom, orange), 0.5));
+}
+
+#[test]
+fn decodes_channels_in_linear_light() {
+    let pixel = Rgba([255, 128, 0, 255]);
+    // Mid sRGB green reflects a fifth of the light
+    assert!((Grayscale::Green.gray(&pixel, true) - 0.2158).abs() < 1e-3);
+    assert!((Grayscale::HslLightness.gray(&pixel, true) - 0.5).abs() < 1e-3);
+    // L* is a perceptual encoding of luminance
+    assert_eq!(
+        Grayscale::CieLightness.gray(&pixel, true),
+        Grayscale::Rec709.gray(&pixel, true)
+    );
+}
+
+#[test]
+fn cie_lightness_is_perceptual() {
+    assert_eq!(gray(Grayscale::CieLightness, [0; 3]), 0.0);
+    assert!((gray(Grayscale::CieLightness, [255; 3]) - 1.0).abs() < 1e-3);
+    assert!((gray(Grayscale::CieLightness, [128; 3]) - 0.5359).abs() < 1e-3);
+}
+
+#[test]
+fn rejects_invalid_weights() {
+    assert_eq!(Grayscale::custom([0.0, 0.0, 0.0]), None);
+    assert_eq!(Grayscale::custom([1.0, -0.5, 0.5]), None);
+    assert_eq!(Grayscale::custom([1.0, f32::NAN, 0.5]), None);
+}
+
+#[test]
+fn looks_up_names() {
+    assert_eq!(Grayscale::from_name("rec601"), Some(Grayscale::Rec601));
+    assert_eq!(
+        Grayscale::from_name("cie-lightness"),
+        Some(Grayscale::CieLightness)
+    );
+    assert_eq!(Grayscale::from_name("hsl"), Some(Grayscale::HslLightness));
+    assert_eq!(Grayscale::from_name("luma"), None);
+}
+
+#[test]
+fn ditherers_use_the_method() {
+    // Pure red is bright on its own channel and dark for every other one
+    let img = RgbaImage::from_pixel(32, 32, Rgba([255, 0, 0, 255]));
+    let ditherers = |grayscale| -> Vec<Box<dyn Ditherer>> {
+        vec![
+            Box::new(
+                ErrorDiffusion::new(DiffusionKernel::FLOYD_STEINBERG).with_grayscale(grayscale),
+            ),
+            Box::new(Riemersma::default().with_grayscale(grayscale)),
+            Box::new(Ordered::bayer(8).unwrap().with_grayscale(grayscale)),
+            Box::new(Halftone::new(60.0, 300.0).with_grayscale(grayscale)),
+        ]
+    };
+
+    for ditherer in ditherers(Grayscale::Red) {
+        assert!(ditherer.dither(&img).pixels().all(|p| p.0[0] == 255));
+    }
+    for ditherer in ditherers(Grayscale::Blue) {
+        assert!(ditherer.dither(&img).pixels().all(|p| p.0[0] == 0));
+    }
+}
@@ -0,0 +1,89 @@
+use dithering::{luminance, Ditherer, DotShape, Halftone, Ordered};
+use image::GrayImage;
+
+mod common;
+
+use common::flat;
+
+const SHAPES: [DotShape; 4] = [
+    DotShape::Round,
+    DotShape::Elliptical,
+    DotShape::Square,
+    DotShape::Line,
+];
+
+fn black_fraction(img: &GrayImage) -> f32 {
+    let black = img.pixels().filter(|p| p.0[0] == 0).count();
+    black as f32 / (img.width() * img.height()) as f32
+}
+
+#[test]
+fn coverage_follows_the_gray_level() {
+    for shape in SHAPES {
+        for angle in [0.0, 15.0, 45.0, 75.0] {
+            // 10 pixel cells
+            let halftone = Halftone::new(30.0, 300.0)
+                .with_angle(angle)
+                .with_shape(shape);
+            let screen = Ordered::new(halftone.threshold_map(120, 120).unwrap());
+
+            for value in [0, 32, 64, 128, 192, 255] {
+                let img = flat(120, 120, value);
+                let fraction = black_fraction(&screen.dither(&img));
+                let expected = 1.0 - luminance(img.get_pixel(0, 0));
+                assert!(
+                    (fraction - expected).abs() < 0.01,
+                    "{:?} at {}°, value {}: {} black",
+                    shape,
+                    angle,
+                    value,
+                    fraction
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn dots_are_clustered_on_the_screen() {
+    // 10 pixel cells at 0° and 80% luminance, so every cell holds one round
+    // dot of ~20 pixels
+    let halftone = Halftone::new(30.0, 300.0).with_angle(0.0);
+    let dithered = halftone.dither(&flat(120, 120, 231));
+
+    for cell_y in 0..12 {
+        for cell_x in 0..12 {
+            let black = (0..100)
+                .filter(|i| {
+                    let (x, y) = (cell_x * 10 + i % 10, cell_y * 10 + i / 10);
+                    dithered.get_pixel(x, y).0[0] == 0
+                })
+                .count();
+            assert!(
+                (18..=22).contains(&black),
+                "cell {} {}: {}",
+                cell_x,
+                cell_y,
+                black
+            );
+
+            // The dot sits in the middle of the cell
+            assert_eq!(dithered.get_pixel(cell_x * 10 + 5, cell_y * 10 + 5).0[0], 0);
+            assert_eq!(dithered.get_pixel(cell_x * 10, cell_y * 10).0[0], 255);
+        }
+    }
+}
+
+#[test]
+fn cells_cut_by_the_edges_keep_their_dot() {
+    for angle in [0.0, 15.0, 45.0] {
+        let halftone = Halftone::new(30.0, 300.0).with_angle(angle);
+        let full = halftone.dither(&flat(40, 40, 231));
+        // Cuts every cell of the second row and column
+        let cut = halftone.dither(&flat(13, 13, 231));
+
+        for (x, y, pixel) in cut.enumerate_pixels() {
+            assert_eq!(pixel, full.get_pixel(x, y), "{}° at {} {}", angle, x, y);
+        }
+    }
+}
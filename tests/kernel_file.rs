@@ -0,0 +1,51 @@
+use dithering::{DiffusionKernel, KernelError};
+
+#[test]
+fn parses_floyd_steinberg() {
+    let kernel: DiffusionKernel = "# Floyd-Steinberg\ndivisor = 16\n. X 7\n3 5 1\n"
+        .parse()
+        .unwrap();
+    assert_eq!(kernel, DiffusionKernel::FLOYD_STEINBERG);
+}
+
+#[test]
+fn divisor_defaults_to_weight_sum() {
+    let kernel: DiffusionKernel = ". * 2\n1 1 .".parse().unwrap();
+    assert_eq!(kernel, DiffusionKernel::SIERRA_LITE);
+}
+
+#[test]
+fn rejects_invalid_kernels() {
+    let cases = [
+        ("3 5 1", "missing marker"),
+        ("X X", "duplicate marker"),
+        ("X 7\n3 5 1", "ragged row"),
+        ("X seven", "syntax"),
+        ("divisor = 0\nX 1", "divisor"),
+        ("divisor = 16\n. X 9\n3 5 1", "weight sum"),
+        ("divisor = 16\n1 X 7\n3 5 1", "backward tap"),
+        ("X 0", "empty"),
+    ];
+
+    for (input, case) in cases {
+        let err = input.parse::<DiffusionKernel>().unwrap_err();
+        let expected = match case {
+            "missing marker" => matches!(err, KernelError::MissingMarker),
+            "duplicate marker" => matches!(err, KernelError::DuplicateMarker { line: 1 }),
+            "ragged row" => matches!(err, KernelError::RaggedRow { line: 2 }),
+            "syntax" => matches!(err, KernelError::Syntax { line: 1, .. }),
+            "divisor" => matches!(err, KernelError::InvalidDivisor(_)),
+            "weight sum" => matches!(err, KernelError::WeightSum(_)),
+            "backward tap" => matches!(err, KernelError::BackwardTap { dx: -1, dy: 0 }),
+            _ => matches!(err, KernelError::Empty),
+        };
+        assert!(expected, "{}: got {:?}", case, err);
+    }
+}
+
+#[test]
+fn built_in_kernels_are_valid() {
+    for (name, kernel) in DiffusionKernel::NAMED {
+        assert!(kernel.validate().is_ok(), "{}", name);
+    }
+}
@@ -0,0 +1,103 @@
+use dithering::color::srgb_to_linear;
+use dithering::{
+    DiffusionKernel, Ditherer, ErrorDiffusion, GrayLevels, Halftone, Ordered, Riemersma, ScanOrder,
+};
+use image::GrayImage;
+
+mod common;
+
+use common::{flat, gradient};
+
+fn ditherers() -> Vec<Box<dyn Ditherer>> {
+    vec![
+        Box::new(ErrorDiffusion::new(DiffusionKernel::FLOYD_STEINBERG)),
+        Box::new(
+            ErrorDiffusion::new(DiffusionKernel::JARVIS_JUDICE_NINKE)
+                .with_scan(ScanOrder::Serpentine),
+        ),
+        Box::new(Riemersma::default()),
+        Box::new(Ordered::bayer(8).unwrap()),
+        Box::new(Halftone::new(60.0, 300.0)),
+    ]
+}
+
+fn count(img: &GrayImage, level: u8) -> usize {
+    img.pixels().filter(|p| p.0[0] == level).count()
+}
+
+#[test]
+fn spaces_levels_evenly() {
+    assert_eq!(GrayLevels::even(4).unwrap().levels(), [0, 85, 170, 255]);
+    assert_eq!(GrayLevels::even(2), Some(GrayLevels::BLACK_WHITE));
+    assert_eq!(GrayLevels::even(256).unwrap().levels().len(), 256);
+    assert_eq!(GrayLevels::even(1), None);
+    assert_eq!(GrayLevels::even(257), None);
+}
+
+#[test]
+fn sorts_custom_levels() {
+    assert_eq!(
+        GrayLevels::new(vec![255, 30, 0, 30]).unwrap().levels(),
+        [0, 30, 255]
+    );
+    assert_eq!(GrayLevels::new(vec![7, 7]), None);
+    assert_eq!(GrayLevels::new(vec![]), None);
+}
+
+#[test]
+fn only_uses_the_levels() {
+    let levels = GrayLevels::new(vec![0, 40, 120, 255]).unwrap();
+    for ditherer in ditherers() {
+        let dithered = ditherer.dither_levels(&gradient(64, 32), &levels);
+        assert!(dithered.pixels().all(|p| levels.levels().contains(&p.0[0])));
+        // Every level shows up somewhere along the gradient
+        for &level in levels.levels() {
+            assert!(count(&dithered, level) > 0, "missing {}", level);
+        }
+    }
+}
+
+#[test]
+fn black_and_white_is_the_default() {
+    for ditherer in ditherers() {
+        assert_eq!(
+            ditherer.dither(&gradient(64, 32)),
+            ditherer.dither_levels(&gradient(64, 32), &GrayLevels::BLACK_WHITE)
+        );
+    }
+}
+
+#[test]
+fn keeps_grays_on_a_level() {
+    let levels = GrayLevels::even(4).unwrap();
+    for ditherer in ditherers() {
+        for &level in levels.levels() {
+            let dithered = ditherer.dither_levels(&flat(64, 64, level), &levels);
+            assert_eq!(count(&dithered, level), dithered.len(), "level {}", level);
+        }
+    }
+}
+
+#[test]
+fn mixes_the_two_closest_levels() {
+    let levels = GrayLevels::even(4).unwrap();
+    let light = |v: u8| srgb_to_linear(f32::from(v) / 255.0);
+
+    for ditherer in ditherers() {
+        let dithered = ditherer.dither_levels(&flat(64, 64, 128), &levels);
+        assert_eq!(count(&dithered, 85) + count(&dithered, 170), dithered.len());
+
+        let mean = dithered.pixels().map(|p| light(p.0[0])).sum::<f32>() / dithered.len() as f32;
+        assert!((mean - light(128)).abs() < 0.01, "mean {}", mean);
+    }
+}
+
+#[test]
+fn ordered_covers_uneven_levels_exactly() {
+    // Halfway between 0 and 100, whatever the spacing of the other levels
+    let levels = GrayLevels::new(vec![0, 100, 255]).unwrap();
+    let ordered = Ordered::bayer(8).unwrap().with_linear(false);
+    let dithered = ordered.dither_levels(&flat(64, 64, 50), &levels);
+    assert_eq!(count(&dithered, 0), 64 * 32);
+    assert_eq!(count(&dithered, 100), 64 * 32);
+}
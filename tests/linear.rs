@@ -0,0 +1,75 @@
+use dithering::{
+    luminance, DiffusionKernel, Ditherer, ErrorDiffusion, Halftone, Ordered, Palette, Riemersma,
+    ScanOrder,
+};
+use image::{RgbImage, Rgba};
+
+mod common;
+
+use common::{flat, white_fraction};
+
+fn ditherers(linear: bool) -> Vec<Box<dyn Ditherer>> {
+    vec![
+        Box::new(ErrorDiffusion::new(DiffusionKernel::FLOYD_STEINBERG).with_linear(linear)),
+        Box::new(
+            ErrorDiffusion::new(DiffusionKernel::JARVIS_JUDICE_NINKE)
+                .with_scan(ScanOrder::Serpentine)
+                .with_linear(linear),
+        ),
+        Box::new(Riemersma::default().with_linear(linear)),
+        Box::new(Ordered::bayer(8).unwrap().with_linear(linear)),
+        Box::new(Halftone::new(60.0, 300.0).with_linear(linear)),
+    ]
+}
+
+#[test]
+fn is_the_default() {
+    assert!(ErrorDiffusion::new(DiffusionKernel::ATKINSON).linear());
+    assert!(Riemersma::default().linear());
+    assert!(Ordered::bayer(4).unwrap().linear());
+    assert!(Halftone::new(60.0, 300.0).linear());
+}
+
+#[test]
+fn luminance_is_linear_light() {
+    assert_eq!(luminance(&Rgba([0, 0, 0, 255])), 0.0);
+    assert!((luminance(&Rgba([255, 255, 255, 255])) - 1.0).abs() < 1e-5);
+    // Mid sRGB gray reflects a fifth of the light of white
+    assert!((luminance(&Rgba([128, 128, 128, 255])) - 0.216).abs() < 0.001);
+    // Green carries most of the luminance
+    assert!((luminance(&Rgba([0, 255, 0, 255])) - 0.7152).abs() < 0.001);
+}
+
+#[test]
+fn white_dots_follow_the_light() {
+    let img = flat(64, 64, 128);
+    for (linear, gamma) in ditherers(true).iter().zip(ditherers(false)) {
+        let linear = white_fraction(&linear.dither(&img));
+        let gamma = white_fraction(&gamma.dither(&img));
+        assert!((linear - 0.216).abs() < 0.03, "linear: {}", linear);
+        assert!((gamma - 0.5).abs() < 0.03, "gamma: {}", gamma);
+    }
+}
+
+#[test]
+fn keeps_black_and_white() {
+    let (black, white) = (flat(64, 64, 0), flat(64, 64, 255));
+    for ditherer in ditherers(true) {
+        assert!(ditherer.dither(&black).pixels().all(|p| p.0[0] == 0));
+        assert!(ditherer.dither(&white).pixels().all(|p| p.0[0] == 255));
+    }
+}
+
+#[test]
+fn palette_dithering_mixes_light() {
+    let palette = Palette::from_hex("#000000,#ffffff").unwrap();
+    let img = flat(64, 64, 128);
+    let white_fraction =
+        |img: RgbImage| img.pixels().filter(|p| p.0 == [255; 3]).count() as f32 / (64.0 * 64.0);
+    for (linear, gamma) in ditherers(true).iter().zip(ditherers(false)) {
+        let linear = white_fraction(linear.dither_palette(&img, &palette));
+        let gamma = white_fraction(gamma.dither_palette(&img, &palette));
+        assert!((linear - 0.216).abs() < 0.03, "linear: {}", linear);
+        assert!((gamma - 0.5).abs() < 0.03, "gamma: {}", gamma);
+    }
+}
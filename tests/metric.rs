@@ -0,0 +1,143 @@
+use dithering::{
+    color::{ciede2000, srgb_to_lab, srgb_to_oklab},
+    DiffusionKernel, Ditherer, ErrorDiffusion, Metric, Ordered, Palette,
+};
+use image::{Rgb, Rgba, RgbaImage};
+
+mod common;
+
+use common::floyd;
+
+const METRICS: [Metric; 5] = [
+    Metric::Rgb,
+    Metric::Redmean,
+    Metric::Cie76,
+    Metric::Ciede2000,
+    Metric::Oklab,
+];
+
+#[test]
+fn matches_ciede2000_reference_data() {
+    // Pairs from Sharma, Wu and Dalal, "The CIEDE2000 Color-Difference Formula"
+    let pairs = [
+        ([50.0, 2.6772, -79.7751], [50.0, 0.0, -82.7485], 2.0425),
+        ([50.0, 2.8361, -74.0200], [50.0, 0.0, -82.7485], 3.4412),
+        ([50.0, 0.0, 0.0], [50.0, -1.0, 2.0], 2.3669),
+        ([50.0, 2.5, 0.0], [73.0, 25.0, -18.0], 27.1492),
+        ([50.0, 2.5, 0.0], [50.0, 3.1736, 0.5854], 1.0000),
+        (
+            [60.2574, -34.0099, 36.2677],
+            [60.4626, -34.1751, 39.4387],
+            1.2644,
+        ),
+        (
+            [22.7233, 20.0904, -46.6940],
+            [23.0331, 14.9730, -42.5619],
+            2.0373,
+        ),
+    ];
+    for (lab1, lab2, expected) in pairs {
+        let delta = ciede2000(lab1, lab2);
+        assert!(
+            (delta - expected).abs() < 1e-3,
+            "{:?} {:?} gave {}",
+            lab1,
+            lab2,
+            delta
+        );
+        assert!((ciede2000(lab2, lab1) - expected).abs() < 1e-3);
+    }
+}
+
+#[test]
+fn converts_to_lab_and_oklab() {
+    let close = |a: [f32; 3], b: [f32; 3], tolerance: f32| {
+        a.iter().zip(b).all(|(x, y)| (x - y).abs() < tolerance)
+    };
+    assert!(close(srgb_to_lab([1.0; 3]), [100.0, 0.0, 0.0], 1e-2));
+    assert!(close(
+        srgb_to_lab([1.0, 0.0, 0.0]),
+        [53.24, 80.09, 67.20],
+        5e-2
+    ));
+    assert!(close(srgb_to_oklab([1.0; 3]), [1.0, 0.0, 0.0], 1e-3));
+    assert!(close(
+        srgb_to_oklab([1.0, 0.0, 0.0]),
+        [0.628, 0.2249, 0.1258],
+        1e-3
+    ));
+}
+
+#[test]
+fn distances_are_symmetric_and_zero_on_equal_colors() {
+    let (a, b) = ([0.2, 0.5, 0.9], [0.8, 0.1, 0.3]);
+    for metric in METRICS {
+        assert_eq!(metric.distance(a, a), 0.0, "{:?}", metric);
+        assert!(metric.distance(a, b) > 0.0);
+        assert!((metric.distance(a, b) - metric.distance(b, a)).abs() < 1e-4);
+    }
+}
+
+#[test]
+fn perceptual_metrics_change_the_nearest_color() {
+    // In RGB a light green is closer to white than to green, but it reads
+    // as green
+    let palette = Palette::from_hex("#000000,#ffffff,#ff0000,#00ff00,#0000ff").unwrap();
+    let light_green = Rgb([128, 224, 128]);
+    assert_eq!(palette.nearest(light_green), 1);
+    for metric in [Metric::Cie76, Metric::Ciede2000, Metric::Oklab] {
+        assert_eq!(
+            palette.clone().with_metric(metric).nearest(light_green),
+            3,
+            "{:?}",
+            metric
+        );
+    }
+}
+
+#[test]
+fn dithers_with_every_metric() {
+    let img = RgbaImage::from_fn(48, 32, |x, y| {
+        Rgba([(x * 5) as u8, (y * 8) as u8, ((x + y) * 3) as u8, 255])
+    });
+    for metric in METRICS {
+        let palette = Palette::PICO_8.with_metric(metric);
+        for ditherer in [
+            &ErrorDiffusion::new(DiffusionKernel::FLOYD_STEINBERG) as &dyn Ditherer,
+            &Ordered::bayer(4).unwrap(),
+        ] {
+            let dithered = ditherer.dither_palette(&img, &palette);
+            assert!(dithered.pixels().all(|p| palette.colors().contains(p)));
+        }
+    }
+}
+
+#[test]
+fn cached_lookups_match_the_palette() {
+    // Palette colors sit on the cache grid, so a flat area of one of them
+    // must come out unchanged
+    let palette = Palette::from_hex("#000000,#ff0000,#00ff00,#0000ff,#ffffff").unwrap();
+    for metric in METRICS {
+        let palette = palette.clone().with_metric(metric);
+        for color in palette.colors() {
+            let [r, g, b] = color.0;
+            let img = RgbaImage::from_pixel(8, 8, Rgba([r, g, b, 255]));
+            let dithered = floyd().dither_palette(&img, &palette);
+            assert!(dithered.pixels().all(|p| p == color), "{:?}", metric);
+        }
+    }
+}
+
+#[test]
+fn looks_up_names() {
+    for (name, metric) in [
+        ("rgb", Metric::Rgb),
+        ("redmean", Metric::Redmean),
+        ("cie76", Metric::Cie76),
+        ("ciede2000", Metric::Ciede2000),
+        ("oklab", Metric::Oklab),
+    ] {
+        assert_eq!(Metric::from_name(name), Some(metric));
+    }
+    assert_eq!(Metric::from_name("cmc"), None);
+}
@@ -0,0 +1,94 @@
+use dithering::color::srgb_to_linear;
+use dithering::{Ditherer, Modulation, ThresholdMap};
+use image::GrayImage;
+
+mod common;
+
+use common::{flat, floyd, gradient, white_fraction};
+
+/// Share of pixels with the same value as their right neighbour, close to 0
+/// for the checkerboard plain diffusion leaves at mid-gray
+fn runs(img: &GrayImage) -> f32 {
+    let (w, h) = img.dimensions();
+    let same = (0..h)
+        .flat_map(|y| (1..w).map(move |x| (x, y)))
+        .filter(|&(x, y)| img.get_pixel(x, y) == img.get_pixel(x - 1, y))
+        .count();
+    same as f32 / ((w - 1) * h) as f32
+}
+
+fn modulations(seed: u64) -> [Modulation; 3] {
+    [
+        Modulation::WhiteNoise {
+            amplitude: 0.5,
+            seed,
+        },
+        Modulation::blue_noise(16, 0.5, seed).unwrap(),
+        Modulation::ZhouFang { seed },
+    ]
+}
+
+#[test]
+fn same_seed_gives_the_same_output() {
+    let img = flat(64, 64, 188);
+    for (modulation, other) in modulations(1).into_iter().zip(modulations(2)) {
+        let dither = |modulation: &Modulation| {
+            floyd()
+                .with_modulation(Some(modulation.clone()))
+                .dither(&img)
+        };
+        assert_eq!(dither(&modulation), dither(&modulation), "{:?}", modulation);
+        assert_ne!(dither(&modulation), dither(&other), "{:?}", modulation);
+    }
+}
+
+#[test]
+fn keeps_the_average() {
+    for value in [60, 150, 188, 220] {
+        let img = flat(64, 64, value);
+        let light = srgb_to_linear(f32::from(value) / 255.0);
+        for modulation in modulations(0) {
+            let dithered = floyd().with_modulation(Some(modulation)).dither(&img);
+            let white = white_fraction(&dithered);
+            assert!((white - light).abs() < 0.02, "{} {}", value, white);
+        }
+    }
+}
+
+#[test]
+fn breaks_up_mid_tone_textures() {
+    // Half of the light, where plain diffusion gives a checkerboard
+    let img = flat(64, 64, 188);
+    assert!(runs(&floyd().dither(&img)) < 0.01);
+    for modulation in modulations(0) {
+        let modulated = runs(&floyd().with_modulation(Some(modulation)).dither(&img));
+        assert!(modulated > 0.05, "{}", modulated);
+    }
+}
+
+#[test]
+fn blue_noise_masks_take_their_size() {
+    let modulation = Modulation::blue_noise(8, 0.5, 4).unwrap();
+    let mask = ThresholdMap::blue_noise(8, 4).unwrap();
+    assert_eq!(
+        modulation,
+        Modulation::BlueNoise {
+            mask,
+            amplitude: 0.5
+        }
+    );
+    assert!(Modulation::blue_noise(0, 0.5, 4).is_none());
+}
+
+#[test]
+fn zero_amplitude_changes_nothing() {
+    let img = gradient(64, 48);
+    let still = Modulation::WhiteNoise {
+        amplitude: 0.0,
+        seed: 3,
+    };
+    assert_eq!(
+        floyd().with_modulation(Some(still)).dither(&img),
+        floyd().dither(&img)
+    );
+}
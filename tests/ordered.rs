@@ -0,0 +1,89 @@
+use dithering::color::linear_to_srgb;
+use dithering::{Ditherer, Ordered, ThresholdMap};
+use image::{ImageBuffer, ImageError};
+
+mod common;
+
+use common::flat;
+
+#[test]
+fn bayer_4_matches_the_classic_matrix() {
+    let map = ThresholdMap::bayer(4).unwrap();
+    let expected = [0, 8, 2, 10, 12, 4, 14, 6, 3, 11, 1, 9, 15, 7, 13, 5];
+
+    for (i, index) in expected.into_iter().enumerate() {
+        let threshold = map.threshold(i as u32 % 4, i as u32 / 4);
+        assert_eq!(threshold, (index as f32 + 0.5) / 16.0);
+    }
+}
+
+#[test]
+fn bayer_uses_every_level_once() {
+    for size in [1, 2, 4, 8, 16, 32, 64] {
+        let map = ThresholdMap::bayer(size).unwrap();
+        let mut indices: Vec<u32> = (0..size * size)
+            .map(|i| (map.threshold(i % size, i / size) * (size * size) as f32) as u32)
+            .collect();
+        indices.sort_unstable();
+        assert!(indices.into_iter().eq(0..size * size), "size {}", size);
+    }
+}
+
+#[test]
+fn bayer_rejects_other_sizes() {
+    for size in [0, 3, 6, 12, 1 << 16, 1 << 31] {
+        assert!(ThresholdMap::bayer(size).is_none(), "size {}", size);
+    }
+}
+
+#[test]
+fn flat_grays_map_to_exact_coverage() {
+    let ordered = Ordered::bayer(4).unwrap();
+
+    for level in 0..=16u32 {
+        // Luminance lands between two thresholds, so exactly `level` of them
+        // are below it
+        let value = (linear_to_srgb(level as f32 / 16.0) * 255.0).round() as u8;
+        let img = flat(8, 8, value);
+        let white = ordered
+            .dither(&img)
+            .pixels()
+            .filter(|p| p.0[0] == 255)
+            .count();
+        assert_eq!(white, level as usize * 4, "value {}", value);
+    }
+}
+
+#[test]
+fn image_levels_are_normalised_by_rank() {
+    let img = ImageBuffer::from_raw(2, 2, vec![10u16, 200, 200, 90]).unwrap();
+    let map = ThresholdMap::from_image(&img).unwrap();
+
+    assert_eq!(map.threshold(0, 0), 0.125);
+    assert_eq!(map.threshold(1, 0), 0.75);
+    assert_eq!(map.threshold(0, 1), 0.75);
+    assert_eq!(map.threshold(1, 1), 0.375);
+    // Tiled over the image
+    assert_eq!(map.threshold(3, 3), 0.375);
+}
+
+#[test]
+fn saved_maps_load_back() {
+    let path = std::env::temp_dir().join(format!("dithering-map-{}.png", std::process::id()));
+    let bayer = ThresholdMap::bayer(8).unwrap();
+    bayer.save(&path).unwrap();
+    let loaded = ThresholdMap::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(loaded, bayer);
+}
+
+#[test]
+fn empty_maps_fail_to_load() {
+    let path = std::env::temp_dir().join(format!("dithering-empty-{}.pgm", std::process::id()));
+    std::fs::write(&path, b"P5\n0 0\n255\n").unwrap();
+    let loaded = ThresholdMap::load(&path);
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(matches!(loaded, Err(ImageError::Parameter(_))));
+}
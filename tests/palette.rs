@@ -0,0 +1,159 @@
+use dithering::color::srgb_to_linear;
+use dithering::{
+    DiffusionKernel, Ditherer, ErrorDiffusion, Halftone, Ordered, Palette, PaletteError, Riemersma,
+    ScanOrder,
+};
+use image::{Rgb, Rgba, RgbaImage};
+
+fn ditherers() -> Vec<Box<dyn Ditherer>> {
+    vec![
+        Box::new(ErrorDiffusion::new(DiffusionKernel::FLOYD_STEINBERG)),
+        Box::new(
+            ErrorDiffusion::new(DiffusionKernel::JARVIS_JUDICE_NINKE)
+                .with_scan(ScanOrder::Serpentine),
+        ),
+        Box::new(Riemersma::default()),
+        Box::new(Ordered::bayer(8).unwrap()),
+        Box::new(Halftone::new(60.0, 300.0)),
+    ]
+}
+
+fn rgb_cube() -> Palette {
+    let mut colors = Vec::new();
+    for r in [0, 255] {
+        for g in [0, 255] {
+            for b in [0, 255] {
+                colors.push(Rgb([r, g, b]));
+            }
+        }
+    }
+    Palette::new(colors).unwrap()
+}
+
+#[test]
+fn parses_hex_lists() {
+    let palette = Palette::from_hex("#000000, ff8000\n#FFFFFF").unwrap();
+    assert_eq!(
+        palette.colors(),
+        [Rgb([0, 0, 0]), Rgb([255, 128, 0]), Rgb([255, 255, 255])]
+    );
+}
+
+#[test]
+fn rejects_invalid_hex_lists() {
+    assert_eq!(Palette::from_hex(" , "), Err(PaletteError::Empty));
+    assert_eq!(
+        Palette::from_hex("#000000,#12345"),
+        Err(PaletteError::InvalidColor("#12345".into()))
+    );
+    assert_eq!(
+        Palette::from_hex("#00000g"),
+        Err(PaletteError::InvalidColor("#00000g".into()))
+    );
+    assert_eq!(Palette::new(Vec::new()), None);
+}
+
+#[test]
+fn only_uses_palette_colors() {
+    let img = RgbaImage::from_fn(40, 30, |x, y| {
+        Rgba([(x * 6) as u8, (y * 8) as u8, 128, 255])
+    });
+    let palette = Palette::from_hex("#1a1c2c,#b13e53,#ef7d57,#ffcd75,#a7f070").unwrap();
+
+    for ditherer in ditherers() {
+        let dithered = ditherer.dither_palette(&img, &palette);
+        assert_eq!(dithered.dimensions(), img.dimensions());
+        assert!(dithered.pixels().all(|p| palette.colors().contains(p)));
+    }
+}
+
+#[test]
+fn keeps_average_color() {
+    let color = [200u8, 90, 40];
+    let img = RgbaImage::from_pixel(64, 64, Rgba([color[0], color[1], color[2], 255]));
+    let light = |c: u8| srgb_to_linear(f32::from(c) / 255.0);
+
+    // Error is diffused in linear light, so that is what averages out
+    for ditherer in ditherers() {
+        let dithered = ditherer.dither_palette(&img, &rgb_cube());
+        for (c, &expected) in color.iter().enumerate() {
+            let mean =
+                dithered.pixels().map(|p| light(p.0[c])).sum::<f32>() / dithered.len() as f32 * 3.0;
+            assert!(
+                (mean - light(expected)).abs() < 0.03,
+                "channel {} averages {} instead of {}",
+                c,
+                mean,
+                expected
+            );
+        }
+    }
+}
+
+#[test]
+fn black_and_white_palette_matches_grayscale() {
+    let img = RgbaImage::from_fn(40, 30, |x, y| {
+        let v = ((x + y) * 3) as u8;
+        Rgba([v, v, v, 255])
+    });
+    let palette = Palette::from_hex("#000000,#ffffff").unwrap();
+
+    for ditherer in ditherers() {
+        let gray = ditherer.dither(&img);
+        let color = ditherer.dither_palette(&img, &palette);
+        assert!(gray
+            .pixels()
+            .zip(color.pixels())
+            .all(|(g, c)| c.0 == [g.0[0]; 3]));
+    }
+}
+
+#[test]
+fn named_palettes_have_distinct_colors() {
+    for (name, palette) in Palette::NAMED {
+        let colors = palette.colors();
+        for (i, color) in colors.iter().enumerate() {
+            assert!(!colors[..i].contains(color), "{} repeats {:?}", name, color);
+        }
+        assert_eq!(Palette::from_name(name).as_ref(), Some(palette));
+    }
+    assert_eq!(Palette::from_name("unknown"), None);
+}
+
+#[test]
+fn named_palette_sizes() {
+    let sizes = [
+        ("cga0", 4),
+        ("cga1-high", 4),
+        ("cga5", 4),
+        ("ega", 16),
+        ("gameboy", 4),
+        ("pico8", 16),
+        ("c64", 16),
+        ("zx-spectrum", 15),
+        ("nes", 55),
+        ("mac", 2),
+        ("web-safe", 216),
+        ("epaper-bwr", 3),
+        ("epaper-bwy", 3),
+        ("acep", 7),
+    ];
+    for (name, size) in sizes {
+        assert_eq!(
+            Palette::from_name(name).unwrap().colors().len(),
+            size,
+            "{}",
+            name
+        );
+    }
+}
+
+#[test]
+fn web_safe_uses_multiples_of_0x33() {
+    let palette = Palette::WEB_SAFE;
+    let colors = palette.colors();
+    assert_eq!(colors[0], Rgb([0x00, 0x00, 0x00]));
+    assert_eq!(colors[1], Rgb([0x00, 0x00, 0x33]));
+    assert_eq!(colors[215], Rgb([0xff, 0xff, 0xff]));
+    assert!(colors.iter().all(|c| c.0.iter().all(|v| v % 0x33 == 0)));
+}
@@ -0,0 +1,190 @@
+use dithering::{Palette, PaletteFileError, PaletteFormat};
+use image::Rgb;
+
+fn sample() -> Palette {
+    Palette::from_hex("#000000,#ff8000,#1d2b53,#ffffff").unwrap()
+}
+
+fn parse(format: PaletteFormat, text: &str) -> Result<Palette, PaletteFileError> {
+    format.parse(text.as_bytes())
+}
+
+#[test]
+fn parses_gpl() {
+    let text = "GIMP Palette\nName: Sample\nColumns: 4\n#\n  0   0   0\tBlack\n255 128   0\n 29  43  83 Navy blue\n255 255 255\n";
+    assert_eq!(parse(PaletteFormat::Gpl, text).unwrap(), sample());
+}
+
+#[test]
+fn parses_jasc() {
+    let text = "JASC-PAL\r\n0100\r\n4\r\n0 0 0\r\n255 128 0\r\n29 43 83\r\n255 255 255\r\n";
+    assert_eq!(parse(PaletteFormat::Jasc, text).unwrap(), sample());
+}
+
+#[test]
+fn parses_paint_net() {
+    let text = "; paint.net Palette File\n; Colors: 4\nFF000000\nFFFF8000\nff1d2b53\n80FFFFFF\n";
+    assert_eq!(parse(PaletteFormat::PaintNet, text).unwrap(), sample());
+}
+
+#[test]
+fn parses_hex() {
+    let text = "000000\nff8000\n\n1D2B53\nffffff";
+    assert_eq!(parse(PaletteFormat::Hex, text).unwrap(), sample());
+}
+
+#[test]
+fn parses_act() {
+    let mut data = vec![0; 768];
+    data[..6].copy_from_slice(&[10, 20, 30, 40, 50, 60]);
+    assert_eq!(PaletteFormat::Act.parse(&data).unwrap().colors().len(), 256);
+
+    data.extend([0, 2, 0xff, 0xff]);
+    assert_eq!(
+        PaletteFormat::Act.parse(&data).unwrap().colors(),
+        [Rgb([10, 20, 30]), Rgb([40, 50, 60])]
+    );
+}
+
+#[test]
+fn parses_aco_color_spaces() {
+    let swatches: [[u16; 5]; 4] = [
+        // RGB
+        [0, 0xffff, 0x8080, 0, 0],
+        // HSB, pure blue
+        [1, 0xaaaa, 0xffff, 0xffff, 0],
+        // CMYK, full cyan ink
+        [2, 0, 0xffff, 0xffff, 0xffff],
+        // Grayscale, 25% black
+        [8, 2500, 0, 0, 0],
+    ];
+    let mut data = vec![0, 1, 0, swatches.len() as u8];
+    for swatch in swatches {
+        for value in swatch {
+            data.extend(value.to_be_bytes());
+        }
+    }
+
+    assert_eq!(
+        PaletteFormat::Aco.parse(&data).unwrap().colors(),
+        [
+            Rgb([255, 128, 0]),
+            Rgb([0, 0, 255]),
+            Rgb([0, 255, 255]),
+            Rgb([191, 191, 191])
+        ]
+    );
+}
+
+#[test]
+fn round_trips_every_format() {
+    for format in PaletteFormat::ALL {
+        let data = format.write(&sample()).unwrap();
+        assert_eq!(format.parse(&data).unwrap(), sample(), "{:?}", format);
+    }
+}
+
+#[test]
+fn round_trips_through_files() {
+    let dir = std::env::temp_dir().join(format!("dithering-palette-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    for format in PaletteFormat::ALL {
+        let path = dir.join(format!("sample.{}", format.extension()));
+        sample().save(&path).unwrap();
+        assert_eq!(Palette::load(&path).unwrap(), sample(), "{:?}", format);
+    }
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn picks_format_from_extension() {
+    assert_eq!(
+        PaletteFormat::from_path("art/pico.GPL").unwrap(),
+        PaletteFormat::Gpl
+    );
+    assert_eq!(
+        PaletteFormat::from_path("art/pico.pal").unwrap(),
+        PaletteFormat::Jasc
+    );
+    assert!(matches!(
+        PaletteFormat::from_path("pico.png"),
+        Err(PaletteFileError::UnknownFormat(ext)) if ext == "png"
+    ));
+}
+
+#[test]
+fn rejects_malformed_files() {
+    let cases = [
+        (PaletteFormat::Gpl, "Paint Palette\n0 0 0", "header"),
+        (PaletteFormat::Gpl, "GIMP Palette\n0 0", "missing channel"),
+        (
+            PaletteFormat::Gpl,
+            "GIMP Palette\n0 0 256",
+            "channel out of range",
+        ),
+        (
+            PaletteFormat::Gpl,
+            "GIMP Palette\nName: Empty\n",
+            "no colors",
+        ),
+        (PaletteFormat::Jasc, "JASC-PAL\n0200\n1\n0 0 0", "version"),
+        (
+            PaletteFormat::Jasc,
+            "JASC-PAL\n0100\n2\n0 0 0",
+            "count mismatch",
+        ),
+        (PaletteFormat::Jasc, "JASC-PAL\n0100", "missing count"),
+        (PaletteFormat::PaintNet, "FF0000", "missing alpha"),
+        (PaletteFormat::Hex, "00000g", "invalid digit"),
+        (PaletteFormat::Hex, "\n\n", "no colors"),
+    ];
+    for (format, text, reason) in cases {
+        assert!(parse(format, text).is_err(), "accepted {}", reason);
+    }
+
+    let binary: [(PaletteFormat, &[u8], &str); 5] = [
+        (PaletteFormat::Act, &[0; 100], "short table"),
+        (PaletteFormat::Act, &[0; 772], "zero count"),
+        (
+            PaletteFormat::Aco,
+            &[0, 1, 0, 2, 0, 0],
+            "truncated swatches",
+        ),
+        (PaletteFormat::Aco, &[0, 3, 0, 0], "unknown version"),
+        (
+            PaletteFormat::Jasc,
+            b"RIFF\x10\x00\x00\x00PAL ",
+            "RIFF palette",
+        ),
+    ];
+    for (format, data, reason) in binary {
+        assert!(format.parse(data).is_err(), "accepted {}", reason);
+    }
+}
+
+#[test]
+fn reports_line_numbers() {
+    let err = parse(
+        PaletteFormat::Gpl,
+        "GIMP Palette\n\n# comment\n0 0 0\n1 x 2\n",
+    )
+    .unwrap_err();
+    assert!(matches!(err, PaletteFileError::Syntax { line: 5, .. }));
+    assert_eq!(err.to_string(), "line 5: invalid channel value x");
+}
+
+#[test]
+fn act_holds_at_most_256_colors() {
+    assert!(matches!(
+        PaletteFormat::Act.write(&Palette::WEB_SAFE),
+        Ok(data) if data.len() == 772
+    ));
+    let palette = Palette::new(vec![Rgb([0, 0, 0]); 300]).unwrap();
+    assert!(matches!(
+        PaletteFormat::Act.write(&palette),
+        Err(PaletteFileError::TooManyColors {
+            count: 300,
+            max: 256
+        })
+    ));
+}
@@ -0,0 +1,114 @@
+use dithering::Quantizer;
+use image::{Rgb, Rgba, RgbaImage};
+
+const QUANTIZERS: [Quantizer; 4] = [
+    Quantizer::MedianCut,
+    Quantizer::Octree,
+    Quantizer::KMeans { seed: 7 },
+    Quantizer::Wu,
+];
+
+const CLUSTERS: [[u8; 3]; 4] = [[20, 30, 200], [230, 40, 40], [40, 200, 60], [240, 240, 220]];
+
+/// Four flat quadrants of the cluster colors, with a little deterministic noise
+fn clusters() -> RgbaImage {
+    RgbaImage::from_fn(64, 64, |x, y| {
+        let [r, g, b] = CLUSTERS[(x / 32 + 2 * (y / 32)) as usize];
+        let noise = ((x * 7 + y * 13) % 9) as u8;
+        Rgba([r + noise, g + noise / 2, b + 8 - noise, 255])
+    })
+}
+
+fn gradient() -> RgbaImage {
+    RgbaImage::from_fn(64, 48, |x, y| {
+        Rgba([(x * 4) as u8, (y * 5) as u8, ((x + y) * 2) as u8, 255])
+    })
+}
+
+fn distance(a: &Rgb<u8>, b: &[u8; 3]) -> i32 {
+    (0..3)
+        .map(|c| (i32::from(a.0[c]) - i32::from(b[c])).pow(2))
+        .sum()
+}
+
+#[test]
+fn finds_clusters() {
+    for quantizer in QUANTIZERS {
+        let palette = quantizer.palette(&clusters(), 4).unwrap();
+        assert_eq!(palette.colors().len(), 4, "{:?}", quantizer);
+        for cluster in CLUSTERS {
+            let closest = palette
+                .colors()
+                .iter()
+                .map(|color| distance(color, &cluster))
+                .min()
+                .unwrap();
+            assert!(closest < 150, "{:?} missed {:?}", quantizer, cluster);
+        }
+    }
+}
+
+#[test]
+fn respects_palette_size() {
+    for quantizer in QUANTIZERS {
+        for colors in [1, 2, 5, 16, 64] {
+            let palette = quantizer.palette(&gradient(), colors).unwrap();
+            let len = palette.colors().len();
+            assert!(
+                len >= 1 && len <= colors,
+                "{:?} gave {} of {}",
+                quantizer,
+                len,
+                colors
+            );
+        }
+    }
+}
+
+#[test]
+fn keeps_images_with_few_colors() {
+    let img = RgbaImage::from_fn(8, 8, |x, _| {
+        if x < 4 {
+            Rgba([1, 2, 3, 255])
+        } else {
+            Rgba([250, 100, 7, 255])
+        }
+    });
+    for quantizer in QUANTIZERS {
+        let palette = quantizer.palette(&img, 8).unwrap();
+        assert_eq!(palette.colors(), [Rgb([1, 2, 3]), Rgb([250, 100, 7])]);
+    }
+}
+
+#[test]
+fn is_deterministic() {
+    for quantizer in QUANTIZERS {
+        assert_eq!(
+            quantizer.palette(&gradient(), 12),
+            quantizer.palette(&gradient(), 12)
+        );
+    }
+    assert_ne!(
+        Quantizer::KMeans { seed: 1 }.palette(&gradient(), 12),
+        Quantizer::KMeans { seed: 2 }.palette(&gradient(), 12)
+    );
+}
+
+#[test]
+fn rejects_empty_requests() {
+    for quantizer in QUANTIZERS {
+        assert_eq!(quantizer.palette(&RgbaImage::new(0, 0), 4), None);
+        assert_eq!(quantizer.palette(&gradient(), 0), None);
+    }
+}
+
+#[test]
+fn looks_up_names() {
+    assert_eq!(Quantizer::from_name("wu"), Some(Quantizer::Wu));
+    assert_eq!(
+        Quantizer::from_name("kmeans").map(|q| q.with_seed(3)),
+        Some(Quantizer::KMeans { seed: 3 })
+    );
+    assert_eq!(Quantizer::Octree.with_seed(3), Quantizer::Octree);
+    assert_eq!(Quantizer::from_name("popularity"), None);
+}
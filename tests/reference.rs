@@ -0,0 +1,170 @@
+use dithering::{
+    DiffusionKernel, Ditherer, DotShape, ErrorDiffusion, Halftone, Ordered, Palette, Riemersma,
+    ScanOrder, ThresholdMap,
+};
+use image::{GrayImage, RgbImage, Rgba, RgbaImage};
+use std::path::PathBuf;
+
+fn gradient() -> RgbaImage {
+    RgbaImage::from_fn(64, 48, |x, y| {
+        Rgba([(x * 4) as u8, (y * 5) as u8, ((x + y) * 2) as u8, 255])
+    })
+}
+
+fn check(name: &str, ditherer: &dyn Ditherer) {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/reference")
+        .join(format!("{}.png", name));
+    let actual = ditherer.dither(&gradient());
+    if std::env::var_os("UPDATE_REFERENCE").is_some() {
+        actual.save(&path).unwrap();
+    }
+    let expected: GrayImage = image::open(&path).unwrap().to_luma8();
+    assert!(
+        actual == expected,
+        "{} differs from {}",
+        name,
+        path.display()
+    );
+}
+
+fn check_palette(name: &str, ditherer: &dyn Ditherer) {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/reference")
+        .join(format!("{}.png", name));
+    let palette = Palette::from_hex("#000000,#ff0000,#00ff00,#0000ff,#ffffff").unwrap();
+    let actual = ditherer.dither_palette(&gradient(), &palette);
+    if std::env::var_os("UPDATE_REFERENCE").is_some() {
+        actual.save(&path).unwrap();
+    }
+    let expected: RgbImage = image::open(&path).unwrap().to_rgb8();
+    assert!(
+        actual == expected,
+        "{} differs from {}",
+        name,
+        path.display()
+    );
+}
+
+fn check_kernel(name: &str, kernel: DiffusionKernel) {
+    check(name, &ErrorDiffusion::new(kernel));
+}
+
+#[test]
+fn floyd_steinberg() {
+    check_kernel("floyd_steinberg", DiffusionKernel::FLOYD_STEINBERG);
+}
+
+#[test]
+fn atkinson() {
+    check_kernel("atkinson", DiffusionKernel::ATKINSON);
+}
+
+#[test]
+fn jarvis_judice_ninke() {
+    check_kernel("jarvis_judice_ninke", DiffusionKernel::JARVIS_JUDICE_NINKE);
+}
+
+#[test]
+fn stucki() {
+    check_kernel("stucki", DiffusionKernel::STUCKI);
+}
+
+#[test]
+fn burkes() {
+    check_kernel("burkes", DiffusionKernel::BURKES);
+}
+
+#[test]
+fn sierra() {
+    check_kernel("sierra", DiffusionKernel::SIERRA);
+}
+
+#[test]
+fn two_row_sierra() {
+    check_kernel("two_row_sierra", DiffusionKernel::TWO_ROW_SIERRA);
+}
+
+#[test]
+fn sierra_lite() {
+    check_kernel("sierra_lite", DiffusionKernel::SIERRA_LITE);
+}
+
+#[test]
+fn fan() {
+    check_kernel("fan", DiffusionKernel::FAN);
+}
+
+#[test]
+fn shiau_fan() {
+    check_kernel("shiau_fan", DiffusionKernel::SHIAU_FAN);
+}
+
+#[test]
+fn shiau_fan_2() {
+    check_kernel("shiau_fan_2", DiffusionKernel::SHIAU_FAN_2);
+}
+
+#[test]
+fn ostromoukhov() {
+    check_kernel("ostromoukhov", DiffusionKernel::OSTROMOUKHOV);
+}
+
+#[test]
+fn floyd_steinberg_serpentine() {
+    check(
+        "floyd_steinberg_serpentine",
+        &ErrorDiffusion::new(DiffusionKernel::FLOYD_STEINBERG).with_scan(ScanOrder::Serpentine),
+    );
+}
+
+#[test]
+fn riemersma() {
+    check("riemersma", &Riemersma::default());
+}
+
+#[test]
+fn bayer_8() {
+    check("bayer_8", &Ordered::bayer(8).unwrap());
+}
+
+#[test]
+fn blue_noise_32() {
+    check(
+        "blue_noise_32",
+        &Ordered::new(ThresholdMap::blue_noise(32, 0).unwrap()),
+    );
+}
+
+#[test]
+fn halftone_round() {
+    check("halftone_round", &Halftone::new(75.0, 600.0));
+}
+
+#[test]
+fn halftone_line() {
+    check(
+        "halftone_line",
+        &Halftone::new(75.0, 600.0)
+            .with_angle(15.0)
+            .with_shape(DotShape::Line),
+    );
+}
+
+#[test]
+fn floyd_steinberg_palette() {
+    check_palette(
+        "floyd_steinberg_palette",
+        &ErrorDiffusion::new(DiffusionKernel::FLOYD_STEINBERG),
+    );
+}
+
+#[test]
+fn riemersma_palette() {
+    check_palette("riemersma_palette", &Riemersma::default());
+}
+
+#[test]
+fn bayer_8_palette() {
+    check_palette("bayer_8_palette", &Ordered::bayer(8).unwrap());
+}
@@ -0,0 +1,31 @@
+use dithering::{Ditherer, Riemersma};
+
+mod common;
+
+use common::flat;
+
+#[test]
+fn handles_any_image_size() {
+    for (w, h) in [
+        (1, 1),
+        (1, 7),
+        (7, 1),
+        (5, 3),
+        (37, 11),
+        (11, 37),
+        (64, 64),
+        (100, 3),
+    ] {
+        let img = flat(w, h, 188);
+        let dithered = Riemersma::default().dither(&img);
+        assert_eq!(dithered.dimensions(), (w, h));
+
+        // Every pixel is visited, so a gray reflecting half the light of white
+        // comes out about half black
+        if w * h >= 64 {
+            let black = dithered.pixels().filter(|p| p.0[0] == 0).count();
+            let fraction = black as f32 / (w * h) as f32;
+            assert!((fraction - 0.5).abs() < 0.05, "{}x{}: {}", w, h, fraction);
+        }
+    }
+}
@@ -0,0 +1,114 @@
+use dithering::color::srgb_to_linear;
+use dithering::{Ditherer, GrayLevels, Palette, Riemersma, Threshold, Thresholding};
+use image::{Rgb, Rgba, RgbaImage};
+
+mod common;
+
+use common::{floyd, white_fraction};
+
+fn gray(w: u32, h: u32, value: impl Fn(u32, u32) -> u8) -> RgbaImage {
+    RgbaImage::from_fn(w, h, |x, y| {
+        let v = value(x, y);
+        Rgba([v, v, v, 255])
+    })
+}
+
+/// Dark scan: paper between 40 and 69, with a few lines of black ink
+fn dark_scan() -> RgbaImage {
+    gray(60, 40, |x, y| if y % 8 == 0 { 0 } else { 40 + x as u8 / 2 })
+}
+
+#[test]
+fn thresholds_halfway_by_default() {
+    let img = gray(256, 1, |x, _| x as u8);
+    for linear in [true, false] {
+        let dithered = Thresholding::new().with_linear(linear).dither(&img);
+        for (x, _, p) in dithered.enumerate_pixels() {
+            let v = x as f32 / 255.0;
+            let v = if linear { srgb_to_linear(v) } else { v };
+            assert_eq!(p.0[0] == 255, v > 0.5, "{} linear {}", x, linear);
+        }
+    }
+}
+
+#[test]
+fn fixed_threshold_is_a_stored_gray_value() {
+    let img = gray(256, 1, |x, _| x as u8);
+    for linear in [true, false] {
+        let dithered = Thresholding::new()
+            .with_threshold(Some(Threshold::Fixed(100)))
+            .with_linear(linear)
+            .dither(&img);
+        for (x, _, p) in dithered.enumerate_pixels() {
+            assert_eq!(p.0[0] == 255, x > 100, "{} linear {}", x, linear);
+        }
+    }
+}
+
+#[test]
+fn automatic_thresholds_rescue_dark_scans() {
+    let img = dark_scan();
+    assert_eq!(white_fraction(&Thresholding::new().dither(&img)), 0.0);
+
+    let with = |threshold| {
+        Thresholding::new()
+            .with_threshold(Some(threshold))
+            .dither(&img)
+    };
+    // Otsu separates the ink from the paper
+    let otsu = with(Threshold::Otsu);
+    for (_, y, p) in otsu.enumerate_pixels() {
+        assert_eq!(p.0[0] == 0, y % 8 == 0, "row {}", y);
+    }
+    let median = white_fraction(&with(Threshold::Median));
+    assert!((median - 0.5).abs() < 0.05, "median {}", median);
+    let mean = white_fraction(&with(Threshold::Mean));
+    assert!(mean > 0.3 && mean < 0.7, "mean {}", mean);
+}
+
+#[test]
+fn diffusion_keeps_the_average_with_any_threshold() {
+    let img = gray(64, 64, |_, _| 150);
+    let light = srgb_to_linear(150.0 / 255.0);
+    let default = floyd().dither(&img);
+
+    for threshold in [
+        Threshold::Fixed(100),
+        Threshold::Fixed(220),
+        Threshold::Otsu,
+    ] {
+        let dithered = floyd().with_threshold(Some(threshold)).dither(&img);
+        let white = white_fraction(&dithered);
+        assert!((white - light).abs() < 0.03, "{:?}: {}", threshold, white);
+        assert_ne!(dithered, default, "{:?}", threshold);
+    }
+
+    let riemersma = Riemersma::default().with_threshold(Some(Threshold::Fixed(220)));
+    assert!((white_fraction(&riemersma.dither(&img)) - light).abs() < 0.03);
+}
+
+#[test]
+fn posterizes_to_levels_and_palettes() {
+    let img = gray(256, 1, |x, _| x as u8);
+    let levels = GrayLevels::even(4).unwrap();
+    let posterized = Thresholding::new()
+        .with_linear(false)
+        .dither_levels(&img, &levels);
+    for (x, _, p) in posterized.enumerate_pixels() {
+        let nearest = ((x as f32 / 85.0).round() * 85.0) as u8;
+        assert_eq!(p.0[0], nearest, "{}", x);
+    }
+
+    let palette = Palette::from_hex("#000000,#ff0000,#ffffff").unwrap();
+    let red = RgbaImage::from_pixel(8, 8, Rgba([230, 30, 20, 255]));
+    let output = Thresholding::new().dither_palette(&red, &palette);
+    assert!(output.pixels().all(|p| *p == Rgb([255, 0, 0])));
+}
+
+#[test]
+fn looks_up_names() {
+    assert_eq!(Threshold::from_name("otsu"), Some(Threshold::Otsu));
+    assert_eq!(Threshold::from_name("mean"), Some(Threshold::Mean));
+    assert_eq!(Threshold::from_name("median"), Some(Threshold::Median));
+    assert_eq!(Threshold::from_name("triangle"), None);
+}
@@ -0,0 +1,81 @@
+use dithering::{DiffusionKernel, Ditherer, ErrorDiffusion, ScanOrder};
+use image::GrayImage;
+
+mod common;
+
+use common::{flat, floyd};
+
+/// Fraction of black pixels in each line of `img`, by columns or by rows
+fn black_fractions(img: &GrayImage, columns: bool) -> Vec<f32> {
+    let (w, h) = img.dimensions();
+    let (lines, len) = if columns { (w, h) } else { (h, w) };
+    (0..lines)
+        .map(|line| {
+            let black = (0..len)
+                .filter(|&i| {
+                    let (x, y) = if columns { (line, i) } else { (i, line) };
+                    img.get_pixel(x, y).0[0] == 0
+                })
+                .count();
+            black as f32 / len as f32
+        })
+        .collect()
+}
+
+#[test]
+fn flat_half_gray_is_half_black() {
+    // Reflects half the light of white
+    let img = flat(96, 64, 188);
+    let expected = 0.5;
+
+    // Variable-coefficient kernels are meant for serpentine scanning only,
+    // see tests/diffusion.rs
+    for (name, kernel) in DiffusionKernel::NAMED
+        .iter()
+        .filter(|(_, kernel)| !kernel.is_variable())
+    {
+        for scan in [ScanOrder::Raster, ScanOrder::Serpentine] {
+            let dithered = ErrorDiffusion::new(kernel.clone())
+                .with_scan(scan)
+                .dither(&img);
+
+            let columns = black_fractions(&dithered, true);
+            let rows = black_fractions(&dithered, false);
+            let total = rows.iter().sum::<f32>() / rows.len() as f32;
+
+            // Atkinson drops a quarter of the error, so it drifts further
+            let tolerance = if *name == "atkinson" { 0.15 } else { 0.05 };
+            assert!(
+                (total - expected).abs() < tolerance,
+                "{} {:?}: {} black",
+                name,
+                scan,
+                total
+            );
+
+            // Skip the first rows and columns, where the error has not built up yet
+            for (i, fraction) in columns[4..].iter().chain(&rows[4..]).enumerate() {
+                assert!(
+                    (fraction - expected).abs() < 0.25,
+                    "{} {:?}: line {} is {} black",
+                    name,
+                    scan,
+                    i,
+                    fraction
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn taps_follow_row_major_order() {
+    // Floyd-Steinberg on a gray reflecting 40% of the light of white: the
+    // black top left pixel pushes the top right one to white, whose error
+    // then has to reach the bottom left pixel before it is visited for it to
+    // end up black
+    let img = flat(2, 2, 170);
+    let dithered = floyd().dither(&img);
+
+    assert_eq!(dithered.into_raw(), vec![0, 255, 0, 0]);
+}